@@ -1,5 +1,7 @@
-use std::collections::HashSet;
-use crate::view::{Transition, View};
+use crate::direction::Direction;
+use crate::pack::Pack;
+use crate::save::Save;
+use crate::view::{EditorView, Transition, View};
 use piston_window::{Button, Key};
 use piston_window::{clear, RenderArgs, UpdateArgs};
 use opengl_graphics::Filter;
@@ -8,16 +10,6 @@ use opengl_graphics::Texture as GlTexture;
 
 const AMBIENT_LUM: f32 = 0.4;
 
-pub fn lerp<T>(left: [T; 4], right: [T; 4], p: T) -> [T; 4]
-where T: std::ops::Sub<Output = T> + std::ops::Mul<Output = T> + std::ops::Add<Output = T> + Copy {  // lmao
-    [
-        (right[0] - left[0]) * p + left[0],
-        (right[1] - left[1]) * p + left[1],
-        (right[2] - left[2]) * p + left[2],
-        (right[3] - left[3]) * p + left[3],
-    ]
-}
-
 pub fn int_lerp(left: [f64; 4], right: [f64; 4], p: f64) -> [f64; 4] {
     [
         ((right[0] - left[0]) * p + left[0]) as i64 as f64,
@@ -44,18 +36,38 @@ pub fn load_texture() -> GlTexture {
 pub struct App {
     view: View,
     held_keys: HeldKeys,
-    completed_levels: HashSet<usize>,  // haha wow this probably shouldn't go here
+    pack: Pack,
+    current_level: Option<usize>,
+    save: Save,
+    /// The editor, set aside while its level is being playtested.
+    playtesting: Option<Box<EditorView>>,
+    clipboard_request: Option<ClipboardRequest>,
+}
+
+/// Something for the window to do with the clipboard, on the game's behalf.
+pub enum ClipboardRequest {
+    Copy(String),
+    Paste,
 }
 
 impl App {
-    pub fn new() -> Self {
+    pub fn new(pack: Pack, save: Save) -> Self {
         App {
-            view: View::title(),
+            view: View::title(save.last_level(&pack)),
             held_keys: HeldKeys::new(),
-            completed_levels: HashSet::new(),
+            pack,
+            current_level: None,
+            save,
+            playtesting: None,
+            clipboard_request: None,
         }
     }
 
+    /// Start out in the level editor instead of on the title screen.
+    pub fn open_editor(&mut self, editor: EditorView) {
+        self.view = View::Editor(Box::new(editor));
+    }
+
     pub fn render(&mut self, args: &RenderArgs, gl: &mut GlGraphics) {
         let v = args.viewport();
         gl.draw(v, |_, gl| {
@@ -65,23 +77,76 @@ impl App {
     }
 
     pub fn update(&mut self, args: &UpdateArgs) {
-        match self.view.update(args, &mut self.held_keys) {
+        let transition = self.view.update(args, &mut self.held_keys);
+        // Leaving a playtest, whether by winning or giving up, goes straight back to the editor.
+        if transition.is_some() && !matches!(transition, Some(Transition::Copy(_) | Transition::Paste)) {
+            if let Some(editor) = self.playtesting.take() {
+                self.view = View::Editor(editor);
+                return;
+            }
+        }
+        match transition {
             Some(Transition::Game(level_id)) => {
-                let top = crate::room::NUM_LEVELS - 1;
-                if level_id > top { self.to_menu(top); }
-                else { self.view = View::game(level_id); }
+                let top = self.pack.levels.len() - 1;
+                if level_id > top { self.open_menu(top); }
+                else {
+                    self.view = View::game(level_id, self.pack.puzzle(level_id));
+                    self.current_level = Some(level_id);
+                    self.save.select(&self.pack, level_id);
+                }
             },
-            Some(Transition::Menu(level_id)) => { self.to_menu(level_id); }
-            Some(Transition::Win(level_id)) => {
-                self.completed_levels.insert(level_id);
+            Some(Transition::Menu(level_id)) => { self.open_menu(level_id); }
+            Some(Transition::Title) => {
+                self.view = View::title(self.save.last_level(&self.pack));
+                self.current_level = None;
+            }
+            Some(Transition::Win(level_id, stats)) => {
+                self.save.complete(&self.pack, level_id, stats);
+            }
+            Some(Transition::Copy(text)) => { self.clipboard_request = Some(ClipboardRequest::Copy(text)); },
+            Some(Transition::Paste) => { self.clipboard_request = Some(ClipboardRequest::Paste); },
+            Some(Transition::Playtest(puzzle)) => {
+                if let View::Editor(editor) = std::mem::replace(&mut self.view, View::game(0, puzzle)) {
+                    self.playtesting = Some(editor);
+                }
             }
             None => (),
         }
     }
 
-    fn to_menu(&mut self, level_id: usize) {
-        let completed_levels = self.completed_levels.iter().copied().collect();
-        self.view = View::menu(level_id, completed_levels);
+    pub fn take_clipboard_request(&mut self) -> Option<ClipboardRequest> {
+        self.clipboard_request.take()
+    }
+
+    /// Hand pasted text to whatever asked for it: moves to play back, if a level's being played.
+    pub fn paste(&mut self, text: &str) {
+        if let View::Game(game) = &mut self.view {
+            game.play_back(text);
+        }
+    }
+
+    fn open_menu(&mut self, level_id: usize) {
+        let completed_levels = self.save.completed_levels(&self.pack);
+        self.view = View::menu(level_id, completed_levels, self.pack.levels.len());
+        self.current_level = None;
+    }
+
+    /// What the window should be called right now.
+    pub fn title(&self) -> String {
+        if let View::Editor(editor) = &self.view {
+            return editor.title();
+        }
+        if let Some(editor) = &self.playtesting {
+            return format!("Playtesting {}", editor.path().display());
+        }
+        if let View::Menu(menu) = &self.view {
+            return format!("{}: {}", self.pack.title, self.pack.levels[menu.selected()].name);
+        }
+        match (self.current_level, &self.pack.author) {
+            (Some(level_id), _) => format!("{}: {}", self.pack.title, self.pack.levels[level_id].name),
+            (None, Some(author)) => format!("{} by {}", self.pack.title, author),
+            (None, None) => self.pack.title.clone(),
+        }
     }
 
     pub fn key_press(&mut self, button: &Button) {
@@ -93,30 +158,22 @@ impl App {
     }
 }
 
-#[derive(PartialEq, Clone)]
-pub enum Direction {
-    North,
-    East,
-    South,
-    West,
-}
-
-impl Direction {
-    pub fn from(&self, x: i32, y: i32) -> (i32, i32) {
-        match self {
-            Direction::North => (x, y - 1),
-            Direction::West => (x - 1, y),
-            Direction::South => (x, y + 1),
-            Direction::East => (x + 1, y),
-        }
-    }
-}
-
 #[derive(PartialEq)]
 pub enum Input {
     Navigate(Direction),
     Accept,
     Reject,
+    Undo,
+    Redo,
+    Pause,
+    Restart,
+    PreviousBrush,
+    NextBrush,
+    CycleLight,
+    Save,
+    Playtest,
+    Copy,
+    Paste,
 }
 
 pub struct HeldKeys {
@@ -155,10 +212,26 @@ impl HeldKeys {
                 Button::Keyboard(Key::D | Key::Right) => Input::Navigate(Direction::East),
                 Button::Keyboard(Key::Space | Key::Z) => Input::Accept,
                 Button::Keyboard(Key::Backspace) => Input::Reject,
+                Button::Keyboard(Key::U) => Input::Undo,
+                Button::Keyboard(Key::Y) => Input::Redo,
+                Button::Keyboard(Key::Escape | Key::P) => Input::Pause,
+                Button::Keyboard(Key::R) => Input::Restart,
+                Button::Keyboard(Key::Q) => Input::PreviousBrush,
+                Button::Keyboard(Key::E) => Input::NextBrush,
+                Button::Keyboard(Key::L) => Input::CycleLight,
+                Button::Keyboard(Key::F2) => Input::Save,
+                Button::Keyboard(Key::T) => Input::Playtest,
+                Button::Keyboard(Key::C) => Input::Copy,
+                Button::Keyboard(Key::V) => Input::Paste,
                 _ => continue,
             };
             // Evict inputs which should not turbo
-            if matches!(&input, Input::Accept | Input::Reject) {
+            if matches!(
+                &input,
+                Input::Accept | Input::Reject | Input::Undo | Input::Redo | Input::Pause | Input::Restart
+                    | Input::PreviousBrush | Input::NextBrush | Input::CycleLight | Input::Save | Input::Playtest
+                    | Input::Copy | Input::Paste,
+            ) {
                 self.ordered_keys.remove(i);
             }
             if !inputs.contains(&input) {
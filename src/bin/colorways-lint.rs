@@ -0,0 +1,77 @@
+use std::path::{Path, PathBuf};
+use std::process;
+use ld49::lint::lint;
+use ld49::pack::Pack;
+use ld49::room::Room;
+
+// Checks level files for mistakes: `cargo run --bin colorways-lint -- [--strict] <files or dirs>`
+// Directories are loaded as packs, so only the levels their manifest lists are checked (or every
+// `.skb` file, without a manifest). Exits non-zero if any level has errors, or any warnings at
+// all with `--strict`.
+
+const USAGE: &str = "usage: colorways-lint [--strict] <level.skb or directory>...";
+
+/// The level files to check for one argument: a pack directory's levels, in the manifest's
+/// order, or else the file itself.
+fn level_files(path: &Path) -> Result<Vec<PathBuf>, String> {
+    if !path.is_dir() { return Ok(vec![path.to_path_buf()]); }
+    let pack = Pack::load(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    Ok(pack.levels.iter().map(|level| path.join(&level.file)).collect())
+}
+
+/// Reports everything wrong with one level, returning how many errors and warnings it had.
+fn check(path: &Path) -> (usize, usize) {
+    let bytes = match std::fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            println!("{}: error: {}", path.display(), e);
+            return (1, 0);
+        },
+    };
+    let puzzle = match Room::from_file(&bytes) {
+        Ok(puzzle) => puzzle,
+        Err(e) => {
+            println!("{}: error: {}", path.display(), e);
+            return (1, 0);
+        },
+    };
+    let (mut errors, mut warnings) = (0, 0);
+    for lint in lint(&puzzle) {
+        if lint.is_error() {
+            errors += 1;
+            println!("{}: error: {}", path.display(), lint);
+        } else {
+            warnings += 1;
+            println!("{}: warning: {}", path.display(), lint);
+        }
+    }
+    (errors, warnings)
+}
+
+fn main() {
+    let mut args: Vec<_> = std::env::args_os().skip(1).collect();
+    let strict = args.first().is_some_and(|arg| arg == "--strict");
+    if strict { args.remove(0); }
+    if args.is_empty() {
+        eprintln!("{}", USAGE);
+        process::exit(2);
+    }
+
+    let (mut errors, mut warnings) = (0, 0);
+    for arg in &args {
+        let files = level_files(arg.as_ref()).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            process::exit(2);
+        });
+        for file in files {
+            let (e, w) = check(&file);
+            errors += e;
+            warnings += w;
+        }
+    }
+
+    println!("{} errors, {} warnings", errors, warnings);
+    if errors > 0 || (strict && warnings > 0) {
+        process::exit(1);
+    }
+}
@@ -0,0 +1,85 @@
+use std::fs;
+use std::path::Path;
+use std::process;
+use ld49::pack::Pack;
+use ld49::room::Room;
+use ld49::xsb;
+
+// Converts Sokoban levels to and from XSB:
+//
+//     cargo run --bin colorways-xsb -- import <collection.xsb> <pack dir>
+//     cargo run --bin colorways-xsb -- export <pack dir or level.skb>...
+//
+// Importing writes a level file for every board in the collection, plus a manifest naming them
+// after their titles. Exporting prints every level as one XSB collection.
+
+const USAGE: &str = "usage: colorways-xsb import <collection.xsb> <pack dir>\n       colorways-xsb export <pack dir or level.skb>...";
+
+fn import(collection: &Path, dir: &Path) -> Result<(), String> {
+    let text = fs::read_to_string(collection).map_err(|e| format!("{}: {}", collection.display(), e))?;
+    let levels = xsb::import(&text).map_err(|e| format!("{}: {}", collection.display(), e))?;
+    let manifest_path = dir.join("manifest.txt");
+    if manifest_path.exists() {
+        return Err(format!("{}: already exists, not overwriting it", manifest_path.display()));
+    }
+    fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+
+    let title = collection.file_stem().map_or_else(|| "Imported".into(), |stem| stem.to_string_lossy());
+    let mut manifest = format!("title {}\n", title);
+    let digits = levels.len().to_string().len().max(2);
+    for (idx, level) in levels.iter().enumerate() {
+        let file = format!("level{:0width$}.skb", idx + 1, width = digits);
+        let path = dir.join(&file);
+        fs::write(&path, &level.source).map_err(|e| format!("{}: {}", path.display(), e))?;
+        match &level.title {
+            Some(title) => manifest += &format!("level {} {}\n", file, title),
+            None => manifest += &format!("level {}\n", file),
+        }
+    }
+    fs::write(&manifest_path, manifest).map_err(|e| format!("{}: {}", manifest_path.display(), e))?;
+    println!("imported {} levels into {}", levels.len(), dir.display());
+    Ok(())
+}
+
+/// Every level to export from one argument, along with its name.
+fn levels(path: &Path) -> Result<Vec<(String, ld49::puzzle::Puzzle)>, String> {
+    if path.is_dir() {
+        let pack = Pack::load(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        return Ok(pack.levels.iter().enumerate()
+            .map(|(idx, level)| (level.name.clone(), pack.puzzle(idx)))
+            .collect());
+    }
+    let bytes = fs::read(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let puzzle = Room::from_file(&bytes).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let name = path.file_stem().map_or_else(String::new, |stem| stem.to_string_lossy().into_owned());
+    Ok(vec![(name, puzzle)])
+}
+
+fn export(paths: &[&Path]) -> Result<(), String> {
+    let mut collection = String::new();
+    for path in paths {
+        for (name, puzzle) in levels(path)? {
+            let board = xsb::export(&puzzle).map_err(|e| format!("{}: {}: {}", path.display(), name, e))?;
+            collection += &format!("{}Title: {}\n\n", board, name);
+        }
+    }
+    print!("{}", collection);
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<_> = std::env::args_os().skip(1).collect();
+    let paths: Vec<&Path> = args.iter().skip(1).map(|arg| arg.as_ref()).collect();
+    let result = match (args.first().and_then(|arg| arg.to_str()), paths.as_slice()) {
+        (Some("import"), [collection, dir]) => import(collection, dir),
+        (Some("export"), paths) if !paths.is_empty() => export(paths),
+        _ => {
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        },
+    };
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
@@ -8,8 +8,11 @@ const YELLOW: ComponentColor = [1., 1., 0., 1.];
 const CYAN: ComponentColor = [0., 1., 1., 1.];
 const MAGENTA: ComponentColor = [1., 0., 1., 1.];
 const WHITE: ComponentColor = [1., 1., 1., 1.];
+static PRIMARIES: [Color; 3] = [Color::Red, Color::Green, Color::Blue];
 
-#[derive(Debug, PartialEq, Clone)]
+/// A color, or equally a mix of the three primary light channels: yellow light is red and
+/// green light together, white is all three, and gray is none at all.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
 pub enum Color {
     Gray,
     Red,
@@ -42,21 +45,32 @@ impl Color {
             Red => [1., 0.2, 0.2, 1.],
             Green => [0.2, 1., 0.2, 1.],
             Blue => [0.2, 0.2, 1., 1.],
+            Yellow => [1., 1., 0.2, 1.],
+            Cyan => [0.2, 1., 1., 1.],
+            Magenta => [1., 0.2, 1., 1.],
             White => WHITE,
-            _ => unimplemented!(),
         }
     }
 
+    /// The primary colors mixed to make this one.
+    pub fn primaries(&self) -> impl Iterator<Item = Color> + '_ {
+        PRIMARIES.iter().filter(move |primary| self.contains(primary)).cloned()
+    }
+
+    /// Which light channel a primary color is, counting red, green, blue from zero.
+    pub fn channel(&self) -> Option<usize> {
+        PRIMARIES.iter().position(|primary| primary == self)
+    }
+
+    /// Whether a primary color is part of this one.
     pub fn contains(&self, subcolor: &Color) -> bool {
-        match (self, subcolor) {
-            (Red, Red) => true,
-            (Green, Green) => true,
-            (Blue, Blue) => true,
-            (Yellow, Red | Green) => true,
-            (Cyan, Green | Blue) => true,
-            (Magenta, Red | Blue) => true,
-            (White, Red | Green | Blue) => true,
-            _ => false,
-        }
+        matches!((self, subcolor),
+            (Red, Red)
+            | (Green, Green)
+            | (Blue, Blue)
+            | (Yellow, Red | Green)
+            | (Cyan, Green | Blue)
+            | (Magenta, Red | Blue)
+            | (White, Red | Green | Blue))
     }
 }
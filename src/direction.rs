@@ -0,0 +1,20 @@
+#[derive(Debug, PartialEq, Clone)]
+pub enum Direction {
+    North,
+    East,
+    South,
+    West,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 4] = [Direction::North, Direction::East, Direction::South, Direction::West];
+
+    pub fn from(&self, x: i32, y: i32) -> (i32, i32) {
+        match self {
+            Direction::North => (x, y - 1),
+            Direction::West => (x - 1, y),
+            Direction::South => (x, y + 1),
+            Direction::East => (x + 1, y),
+        }
+    }
+}
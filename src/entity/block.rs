@@ -1,8 +1,6 @@
 use piston_window::{Image, UpdateArgs};
-use crate::app::Direction;
 use crate::color::Color;
-use crate::entity::Entity;
-use crate::view::{GameAction, GameView};
+use crate::direction::Direction;
 
 const TILE_SIZE: f64 = 16.;
 const BLOCK_WIDTH: f64 = TILE_SIZE;
@@ -43,14 +41,11 @@ impl Block {
     }
 
     pub fn update(&mut self, args: &UpdateArgs) {
-        match self.state {
-            State::Slide(p) => {
-                let new_p = p + args.dt * 5.;
-                self.state = if new_p >= 1. { State::Idle }
-                    else { State::Slide(new_p) };
-            },
-            _ => ()
-        };
+        if let State::Slide(p) = self.state {
+            let new_p = p + args.dt * 5.;
+            self.state = if new_p >= 1. { State::Idle }
+                else { State::Slide(new_p) };
+        }
     }
 
     fn sub_position(&self) -> (f64, f64) {
@@ -66,22 +61,7 @@ impl Block {
         (0., 0.)
     }
 
-    pub fn is_approachable(&self, direction: &Direction, view: &GameView) -> Option<GameAction> {
-        if view.tile_in_light(self.x, self.y, &self.color) { return None; }
-        let (nx, ny) = direction.from(self.x, self.y);
-        if !view.tile_is_passable(nx, ny) { return Some(GameAction::Stop); }
-        match view.entity_at(nx, ny)? {
-            Entity::Water(_) => Some(GameAction::DestroyBoth(view.entity_id_at(nx, ny)?, 0)),
-            _ => Some(GameAction::Stop),
-        }
-    }
-
-    pub fn on_approach(&mut self, direction: &Direction) -> Option<GameAction> {
-        self.push(direction);
-        None
-    }
-
-    fn push(&mut self, direction: &Direction) {
+    pub fn push(&mut self, direction: &Direction) {
         match direction {
             Direction::North => self.y -= 1,
             Direction::West => self.x -= 1,
@@ -1,7 +1,8 @@
 use piston_window::{Image, UpdateArgs};
-use crate::app::Direction;
-use crate::view::{GameAction, GameView};
+use crate::direction::Direction;
 use crate::entity;
+use crate::lighting::Lighting;
+use crate::puzzle::{Kind, Piece};
 
 // The boilerplatenest file. Try to stay out of here as much as possible.
 pub enum Entity {
@@ -9,17 +10,44 @@ pub enum Entity {
     Exit(entity::Exit),
     Lightbulb(entity::Lightbulb),
     LightSwitch(entity::LightSwitch),
+    Mirror(entity::Mirror),
+    Paint(entity::Paint),
+    Pit(entity::Pit),
     Water(entity::Water),
 }
 use Entity::*;
 
 impl Entity {
+    pub fn from_piece(piece: &Piece, lighting: &Lighting) -> Self {
+        let Piece { x, y, kind } = piece;
+        let (x, y) = (*x, *y);
+        match kind {
+            Kind::Block(color) => Block(entity::Block::new(x, y, color.clone())),
+            Kind::Exit => Exit(entity::Exit::new(x, y)),
+            Kind::Lightbulb(color) => {
+                Lightbulb(entity::Lightbulb::new(x, y, color.clone(), lighting.fan(x, y).to_vec()))
+            },
+            Kind::Lamp(color) => {
+                Lightbulb(entity::Lightbulb::new_lamp(x, y, color.clone(), lighting.fan(x, y).to_vec()))
+            },
+            Kind::LightSwitch(color) => LightSwitch(entity::LightSwitch::new(x, y, color.clone())),
+            Kind::PressurePlate(color) => LightSwitch(entity::LightSwitch::new_plate(x, y, color.clone())),
+            Kind::Mirror(slant) => Mirror(entity::Mirror::new(x, y, slant.clone())),
+            Kind::Paint(color) => Paint(entity::Paint::new(x, y, color.clone())),
+            Kind::Pit => Pit(entity::Pit::new(x, y)),
+            Kind::Water => Water(entity::Water::new(x, y)),
+        }
+    }
+
     pub fn sprite(&self) -> Image {
         match self {
             Block(e) => e.sprite(),
             Exit(e) => e.sprite(),
             Lightbulb(e) => e.sprite(),
             LightSwitch(e) => e.sprite(),
+            Mirror(e) => e.sprite(),
+            Paint(e) => e.sprite(),
+            Pit(e) => e.sprite(),
             Water(e) => e.sprite(),
         }
     }
@@ -30,47 +58,19 @@ impl Entity {
             Exit(e) => e.update(args),
             Lightbulb(e) => e.update(args),
             LightSwitch(e) => e.update(args),
+            Mirror(e) => e.update(args),
+            Paint(e) => e.update(args),
+            Pit(e) => e.update(args),
             Water(e) => e.update(args),
         }
     }
 
-    pub fn is_approachable(&self, direction: &Direction, game: &GameView) -> Option<GameAction> {
-        match self {
-            Block(e) => e.is_approachable(direction, game),
-            Exit(e) => e.is_approachable(direction, game),
-            Lightbulb(e) => e.is_approachable(direction, game),
-            LightSwitch(e) => e.is_approachable(direction, game),
-            Water(e) => e.is_approachable(direction, game),
-        }
-    }
-
-    pub fn on_approach(&mut self, direction: &Direction) -> Option<GameAction> {
-        match self {
-            Block(e) => e.on_approach(direction),
-            Exit(e) => e.on_approach(direction),
-            Lightbulb(e) => e.on_approach(direction),
-            LightSwitch(e) => e.on_approach(direction),
-            Water(e) => e.on_approach(direction),
-        }
-    }
-
-    pub fn x(&self) -> i32 {
-        match self {
-            Block(e) => e.x,
-            Exit(e) => e.x,
-            Lightbulb(e) => e.x,
-            LightSwitch(e) => e.x,
-            Water(e) => e.x,
-        }
-    }
-
-    pub fn y(&self) -> i32 {
+    pub fn push(&mut self, direction: &Direction) {
         match self {
-            Block(e) => e.y,
-            Exit(e) => e.y,
-            Lightbulb(e) => e.y,
-            LightSwitch(e) => e.y,
-            Water(e) => e.y,
+            Block(e) => e.push(direction),
+            Lightbulb(e) => e.push(direction),
+            Mirror(e) => e.push(direction),
+            _ => (),
         }
     }
 }
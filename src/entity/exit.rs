@@ -1,29 +1,27 @@
 use piston_window::{Image, UpdateArgs};
-use crate::app::Direction;
-use crate::view::{GameAction, GameView};
 
 const TILE_SIZE: f64 = 16.;
 const EXIT: [f64; 4] = [16., 0., TILE_SIZE, TILE_SIZE];
+const LOCKED: [f32; 4] = [1., 1., 1., 0.35];
 
 pub struct Exit {
     pub x: i32,
     pub y: i32,
+    /// Faded out while there are still goals to fill.
+    pub locked: bool,
 }
 
 impl Exit {
     pub fn new(x: i32, y: i32) -> Self {
-        Self { x, y }
+        Self { x, y, locked: false }
     }
     pub fn sprite(&self) -> Image {
         let x = self.x as f64 * TILE_SIZE;
         let y = self.y as f64 * TILE_SIZE;
-        Image::new()
+        let image = if self.locked { Image::new_color(LOCKED) } else { Image::new() };
+        image
             .src_rect(EXIT)
             .rect([x, y, TILE_SIZE, TILE_SIZE])
     }
     pub fn update(&mut self, _args: &UpdateArgs) {}
-    pub fn is_approachable(&self, _direction: &Direction, _game: &GameView) -> Option<GameAction> { None }
-    pub fn on_approach(&mut self, _direction: &Direction) -> Option<GameAction> {
-        Some(GameAction::Win)
-    }
 }
@@ -1,17 +1,38 @@
 use opengl_graphics::GlGraphics;
 use piston_window::{Context, DrawState, Image, Polygon, UpdateArgs};
 use piston_window::draw_state::Blend;
-use crate::app::{Direction, lerp};
 use crate::color::Color;
-use crate::view::{GameAction, GameView};
+use crate::direction::Direction;
+use crate::line_of_sight::Triangle;
 
 const TILE_SIZE: f64 = 16.;
-const LIGHTBULB_ON: [f64; 4] = [16., 16., TILE_SIZE, TILE_SIZE];
-const LIGHTBULB_RISING_1: [f64; 4] = [16., 48., TILE_SIZE, TILE_SIZE];
-const LIGHTBULB_RISING_2: [f64; 4] = [16., 32., TILE_SIZE, TILE_SIZE];
-const LIGHTBULB_FALLING_1: [f64; 4] = [32., 32., TILE_SIZE, TILE_SIZE];
-const LIGHTBULB_FALLING_2: [f64; 4] = [32., 48., TILE_SIZE, TILE_SIZE];
-const LIGHTBULB_OFF: [f64; 4] = [16., 64., TILE_SIZE, TILE_SIZE];
+const LIGHTBULB: Frames = Frames {
+    on: [16., 16., TILE_SIZE, TILE_SIZE],
+    rising_1: [16., 48., TILE_SIZE, TILE_SIZE],
+    rising_2: [16., 32., TILE_SIZE, TILE_SIZE],
+    falling_1: [32., 32., TILE_SIZE, TILE_SIZE],
+    falling_2: [32., 48., TILE_SIZE, TILE_SIZE],
+    off: [16., 64., TILE_SIZE, TILE_SIZE],
+};
+const LAMP: Frames = Frames {
+    on: [80., 80., TILE_SIZE, TILE_SIZE],
+    rising_1: [96., 80., TILE_SIZE, TILE_SIZE],
+    rising_2: [112., 80., TILE_SIZE, TILE_SIZE],
+    falling_1: [80., 96., TILE_SIZE, TILE_SIZE],
+    falling_2: [96., 96., TILE_SIZE, TILE_SIZE],
+    off: [112., 96., TILE_SIZE, TILE_SIZE],
+};
+// How much a fully lit fan brightens what's under it.
+const LIGHT_STRENGTH: f32 = 0.35;
+
+struct Frames {
+    on: [f64; 4],
+    rising_1: [f64; 4],
+    rising_2: [f64; 4],
+    falling_1: [f64; 4],
+    falling_2: [f64; 4],
+    off: [f64; 4],
+}
 
 enum State {
     On,
@@ -25,34 +46,69 @@ pub struct Lightbulb {
     pub y: i32,
     pub color: Color,
     state: State,
-    light_polygon: Vec<[f64; 2]>,
+    light: Vec<Triangle>,
+    // Lamps are bulbs that can be pushed around. How far along a push they are, if they're moving.
+    lamp: bool,
+    slide: Option<f64>,
+    facing: Direction,
 }
 
 impl Lightbulb {
-    pub fn new(x: i32, y: i32, color: Color, light_polygon: Vec<[f64; 2]>) -> Self {
-        Self { x, y, color, state: State::Off, light_polygon }
+    pub fn new(x: i32, y: i32, color: Color, light: Vec<Triangle>) -> Self {
+        Self { x, y, color, state: State::Off, light, lamp: false, slide: None, facing: Direction::East }
+    }
+
+    pub fn new_lamp(x: i32, y: i32, color: Color, light: Vec<Triangle>) -> Self {
+        Self { lamp: true, ..Self::new(x, y, color, light) }
     }
 
     pub fn sprite(&self) -> Image {
+        let frames = if self.lamp { &LAMP } else { &LIGHTBULB };
         let src = match self.state {
-            State::On => LIGHTBULB_ON,
-            State::Off => LIGHTBULB_OFF,
-            State::Rising(p) if p <= 0.2 => { LIGHTBULB_OFF },
-            State::Rising(p) if p <= 0.5 => { LIGHTBULB_RISING_1 },
-            State::Rising(p) if p <= 0.8 => { LIGHTBULB_RISING_2 },
-            State::Rising(_) => { LIGHTBULB_ON },
-            State::Falling(p) if p <= 0.2 => { LIGHTBULB_FALLING_1 },
-            State::Falling(p) if p <= 0.4 => { LIGHTBULB_FALLING_2 },
-            State::Falling(_) => { LIGHTBULB_OFF },
+            State::On => frames.on,
+            State::Off => frames.off,
+            State::Rising(p) if p <= 0.2 => { frames.off },
+            State::Rising(p) if p <= 0.5 => { frames.rising_1 },
+            State::Rising(p) if p <= 0.8 => { frames.rising_2 },
+            State::Rising(_) => { frames.on },
+            State::Falling(p) if p <= 0.2 => { frames.falling_1 },
+            State::Falling(p) if p <= 0.4 => { frames.falling_2 },
+            State::Falling(_) => { frames.off },
         };
         let x = self.x as f64 * TILE_SIZE;
         let y = self.y as f64 * TILE_SIZE;
+        let (sx, sy) = self.sub_position();
         Image::new_color(self.color.as_component())
             .src_rect(src)
-            .rect([x, y, TILE_SIZE, TILE_SIZE])
+            .rect([x - sx, y - sy, TILE_SIZE, TILE_SIZE])
+    }
+
+    fn sub_position(&self) -> (f64, f64) {
+        if let Some(p) = self.slide {
+            let progress = ((1. - p) * TILE_SIZE) as i8 as f64;
+            return match self.facing {
+                Direction::North => (0., -progress),
+                Direction::East => (progress, 0.),
+                Direction::South => (0., progress),
+                Direction::West => (-progress, 0.),
+            }
+        }
+        (0., 0.)
+    }
+
+    pub fn push(&mut self, direction: &Direction) {
+        let (x, y) = direction.from(self.x, self.y);
+        self.x = x;
+        self.y = y;
+        self.slide = Some(0.);
+        self.facing = direction.clone();
     }
 
     pub fn update(&mut self, args: &UpdateArgs) {
+        if let Some(p) = self.slide {
+            let new_p = p + args.dt * 5.;
+            self.slide = if new_p >= 1. { None } else { Some(new_p) };
+        }
         match self.state {
             State::On | State::Off => (),
             State::Rising(p) => {
@@ -68,27 +124,21 @@ impl Lightbulb {
         }
     }
 
-    pub fn is_approachable(&self, _direction: &Direction, _game: &GameView) -> Option<GameAction> {
-        Some(GameAction::Stop)
-    }
-    pub fn on_approach(&mut self, _direction: &Direction) -> Option<GameAction> { None }
-
-    pub fn draw_light_base(&self, context: &Context, gl: &mut GlGraphics) {
+    /// Light is added on top of whatever's underneath, so overlapping fans mix:
+    /// red and green make yellow, and all three make white.
+    pub fn draw_light(&self, context: &Context, gl: &mut GlGraphics) {
+        let [r, g, b, _] = self.color.as_light_component();
         self.draw_light_fan(
-            [0.3, 0.3, 0.3, 1.],
-            &DrawState::default(),
+            [r, g, b, LIGHT_STRENGTH * self.light_alpha()],
+            &DrawState::default().blend(Blend::Lighter),
             context,
             gl,
         );
     }
 
-    pub fn draw_light(&self, context: &Context, gl: &mut GlGraphics) {
-        self.draw_light_fan(
-            self.color.as_light_component(),
-            &DrawState::default().blend(Blend::Multiply),
-            context,
-            gl,
-        );
+    /// Reshape the light, for when something's moved into or out of its way.
+    pub fn set_light(&mut self, light: Vec<Triangle>) {
+        self.light = light;
     }
 
     pub fn turn_on(&mut self) {
@@ -99,6 +149,11 @@ impl Lightbulb {
         self.state = State::Falling(0.);
     }
 
+    /// Skip straight to fully on, with no warmup.
+    pub fn snap_on(&mut self) {
+        self.state = State::On;
+    }
+
     fn light_alpha(&self) -> f32 {
         match self.state {
             State::On => 1.,
@@ -110,15 +165,11 @@ impl Lightbulb {
 
     fn draw_light_fan(&self, color: [f32; 4], state: &DrawState, context: &Context, gl: &mut GlGraphics) {
         if matches!(self.state, State::Off) { return; }
-        let color = lerp([1., 1., 1., 1.], color, self.light_alpha());
-        // Need to triangulate the polygon: opengl doesn't draw concave polygons.
-        // Fortunately we axiomatically have a point that can see all vertexes: the sprite center.
-        // TODO: look into how to accomplish a "fan"
-        let center = [(self.x as f64 + 0.5) * TILE_SIZE, (self.y as f64 + 0.5) * TILE_SIZE];
+        // The light comes pre-triangulated: opengl doesn't draw concave polygons.
         let polygon = Polygon::new(color);
-        for v in self.light_polygon.windows(2) {
+        for triangle in &self.light {
             polygon.draw(
-                &[v[0], v[1], center],
+                triangle,
                 state,
                 context.transform,
                 gl,
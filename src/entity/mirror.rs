@@ -0,0 +1,72 @@
+use piston_window::{Image, UpdateArgs};
+use crate::direction::Direction;
+use crate::puzzle::Slant;
+
+const TILE_SIZE: f64 = 16.;
+const MIRROR_FORWARD: [f64; 4] = [224., 176., TILE_SIZE, TILE_SIZE];
+const MIRROR_BACK: [f64; 4] = [240., 176., TILE_SIZE, TILE_SIZE];
+
+enum State {
+    Idle,
+    Slide(f64),
+}
+
+pub struct Mirror {
+    pub x: i32,
+    pub y: i32,
+    state: State,
+    facing: Direction,
+    slant: Slant,
+}
+
+impl Mirror {
+    pub fn new(x: i32, y: i32, slant: Slant) -> Self {
+        Self {
+            x, y, state: State::Idle,
+            facing: Direction::East,
+            slant,
+        }
+    }
+
+    pub fn sprite(&self) -> Image {
+        let src = match self.slant {
+            Slant::Forward => MIRROR_FORWARD,
+            Slant::Back => MIRROR_BACK,
+        };
+        let x = self.x as f64 * TILE_SIZE;
+        let y = self.y as f64 * TILE_SIZE;
+        let (sx, sy) = self.sub_position();
+        Image::new()
+            .src_rect(src)
+            .rect([x - sx, y - sy, TILE_SIZE, TILE_SIZE])
+    }
+
+    pub fn update(&mut self, args: &UpdateArgs) {
+        if let State::Slide(p) = self.state {
+            let new_p = p + args.dt * 5.;
+            self.state = if new_p >= 1. { State::Idle }
+                else { State::Slide(new_p) };
+        }
+    }
+
+    fn sub_position(&self) -> (f64, f64) {
+        if let State::Slide(p) = self.state {
+            let progress = ((1. - p) * TILE_SIZE) as i8 as f64;
+            return match self.facing {
+                Direction::North => (0., -progress),
+                Direction::East => (progress, 0.),
+                Direction::South => (0., progress),
+                Direction::West => (-progress, 0.),
+            }
+        }
+        (0., 0.)
+    }
+
+    pub fn push(&mut self, direction: &Direction) {
+        let (x, y) = direction.from(self.x, self.y);
+        self.x = x;
+        self.y = y;
+        self.state = State::Slide(0.);
+        self.facing = direction.clone();
+    }
+}
@@ -1,8 +1,12 @@
 mod block;
+#[allow(clippy::module_inception)]
 mod entity;
 mod exit;
 mod lightbulb;
 mod lightswitch;
+mod mirror;
+mod paint;
+mod pit;
 mod player;
 mod water;
 
@@ -11,5 +15,8 @@ pub use entity::Entity;
 pub use exit::Exit;
 pub use lightbulb::Lightbulb;
 pub use lightswitch::LightSwitch;
+pub use mirror::Mirror;
+pub use paint::Paint;
+pub use pit::Pit;
 pub use player::Player;
 pub use water::Water;
@@ -0,0 +1,27 @@
+use piston_window::{Image, UpdateArgs};
+use crate::color::Color;
+
+const TILE_SIZE: f64 = 16.;
+const PAINT: [f64; 4] = [160., 176., TILE_SIZE, TILE_SIZE];
+
+pub struct Paint {
+    pub x: i32,
+    pub y: i32,
+    color: Color,
+}
+
+impl Paint {
+    pub fn new(x: i32, y: i32, color: Color) -> Self {
+        Self { x, y, color }
+    }
+
+    pub fn sprite(&self) -> Image {
+        let x = self.x as f64 * TILE_SIZE;
+        let y = self.y as f64 * TILE_SIZE;
+        Image::new_color(self.color.as_component())
+            .src_rect(PAINT)
+            .rect([x, y, TILE_SIZE, TILE_SIZE])
+    }
+
+    pub fn update(&mut self, _args: &UpdateArgs) {}
+}
@@ -0,0 +1,25 @@
+use piston_window::{Image, UpdateArgs};
+
+const TILE_SIZE: f64 = 16.;
+const PIT: [f64; 4] = [144., 176., TILE_SIZE, TILE_SIZE];
+
+pub struct Pit {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl Pit {
+    pub fn new(x: i32, y: i32) -> Self {
+        Self { x, y }
+    }
+
+    pub fn sprite(&self) -> Image {
+        let x = self.x as f64 * TILE_SIZE;
+        let y = self.y as f64 * TILE_SIZE;
+        Image::new()
+            .src_rect(PIT)
+            .rect([x, y, TILE_SIZE, TILE_SIZE])
+    }
+
+    pub fn update(&mut self, _args: &UpdateArgs) {}
+}
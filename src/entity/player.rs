@@ -1,6 +1,6 @@
 use piston_window::{Image, UpdateArgs};
-use crate::app::Direction;
-use crate::app::Direction::*;
+use crate::direction::Direction;
+use crate::direction::Direction::*;
 
 // fn flip(coords: [f64; 4]) -> [f64; 4] {
 //     [coords[0] + coords[2], coords[1], -coords[2], coords[3]]
@@ -66,14 +66,11 @@ impl Player {
     }
 
     pub fn update(&mut self, args: &UpdateArgs) {
-        match self.state {
-            State::Walk(p) => {
-                let new_p = p + args.dt * 5.;
-                self.state = if new_p >= 1. { State::Idle }
-                    else { State::Walk(new_p) };
-            },
-            _ => ()
-        };
+        if let State::Walk(p) = self.state {
+            let new_p = p + args.dt * 5.;
+            self.state = if new_p >= 1. { State::Idle }
+                else { State::Walk(new_p) };
+        }
     }
 
     fn sprite_src(&self) -> [f64; 4] {
@@ -137,6 +134,13 @@ impl Player {
         }
     }
 
+    /// Drop the player onto a tile, abandoning any walk in progress.
+    pub fn place(&mut self, x: i32, y: i32) {
+        self.x = x;
+        self.y = y;
+        self.state = State::Idle;
+    }
+
     pub fn can_walk(&self) -> bool {
         matches!(&self.state, State::Idle)
     }
@@ -1,7 +1,4 @@
 use piston_window::{Image, UpdateArgs};
-use crate::app::Direction;
-use crate::color::Color;
-use crate::view::{GameAction, GameView};
 
 const TILE_SIZE: f64 = 16.;
 const WATER: [f64; 4] = [32., 64., TILE_SIZE, TILE_SIZE];
@@ -25,11 +22,4 @@ impl Water {
     }
 
     pub fn update(&mut self, _args: &UpdateArgs) {}
-    pub fn is_approachable(&self, _direction: &Direction, view: &GameView) -> Option<GameAction> {
-        if view.tile_in_light(self.x, self.y, &Color::Blue) { return None; }
-        Some(GameAction::Stop)
-    }
-    pub fn on_approach(&mut self, _direction: &Direction) -> Option<GameAction> {
-        None
-    }
 }
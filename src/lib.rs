@@ -0,0 +1,15 @@
+// The parts of Colorways that don't need a window: level files, the rules, and tools for
+// checking and converting levels. The game itself and the `colorways-lint` and `colorways-xsb`
+// tools all build on these.
+
+pub mod color;
+pub mod direction;
+pub mod lighting;
+pub mod line_of_sight;
+pub mod lint;
+pub mod lurd;
+pub mod pack;
+pub mod puzzle;
+pub mod room;
+pub mod solver;
+pub mod xsb;
@@ -0,0 +1,59 @@
+use std::collections::HashMap;
+use crate::color::Color;
+use crate::line_of_sight::{line_of_sight, Triangle};
+use crate::puzzle::Piece;
+use crate::room::Room;
+
+// Where the light falls. Walls never move, but pieces can get between a bulb and the floor,
+// so this is worked out afresh for each arrangement of the pieces that matter to the light.
+// Each channel is traced separately, since a tinted filter only stops some of them, and light
+// is followed as it bounces off mirrors.
+
+pub struct Lighting {
+    width: usize,
+    sees_color: Vec<[bool; 3]>,
+    /// The area each bulb or lamp lights, reflections and all, by its position.
+    fans: HashMap<(i32, i32), Vec<Triangle>>,
+}
+
+impl Lighting {
+    /// Shine every bulb among the pieces, with whatever stops its color of light in the way.
+    pub fn new(room: &Room, pieces: &[Piece]) -> Self {
+        let (width, height) = (room.width(), room.height());
+        let occluders: Vec<Vec<_>> = Color::White.primaries()
+            .map(|primary| room.occluders(pieces, &primary).0.iter()
+                .flat_map(|polygon| polygon.exterior().lines())
+                .collect())
+            .collect();
+        let mirrors = room.mirrors(pieces);
+        let mut sees_color = vec![[false, false, false]; width * height];
+        let mut fans = HashMap::new();
+        for piece in pieces {
+            let cidx = match piece.kind.light_color().and_then(Color::channel) {
+                Some(cidx) => cidx,
+                None => { continue; },
+            };
+            let visibility = line_of_sight(piece.x, piece.y, width, height, &occluders[cidx], &mirrors);
+            for idx in visibility.tiles {
+                sees_color[idx][cidx] = true;
+            }
+            fans.insert((piece.x, piece.y), visibility.triangles);
+        }
+        Lighting { width, sees_color, fans }
+    }
+
+    pub fn tile_in_light(&self, x: i32, y: i32, color: &Color) -> bool {
+        let cidx = match color.channel() {
+            Some(cidx) => cidx,
+            None => { return false; },
+        };
+        if x < 0 || y < 0 || x as usize >= self.width { return false; }
+        let idx = self.width * (y as usize) + x as usize;
+        self.sees_color.get(idx).is_some_and(|&arr| arr[cidx])
+    }
+
+    /// The area lit by the bulb or lamp at the given tile, empty if there's no light there.
+    pub fn fan(&self, x: i32, y: i32) -> &[Triangle] {
+        self.fans.get(&(x, y)).map_or(&[], |fan| fan)
+    }
+}
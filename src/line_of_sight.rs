@@ -1,41 +1,426 @@
+use std::cmp::Ordering;
+use std::collections::BTreeSet;
 use geo::polygon;
 use geo::prelude::Contains;
-use geo_visibility::Visibility as _;
+use geo::{Coordinate, Line, LineString};
 
 const TILE_SIZE: f64 = 16.;
+// How many times light bounces between mirrors before it's too dim to bother following.
+const MAX_BOUNCES: usize = 4;
+// Close enough to count as the same spot, given floating point.
+const EPSILON: f64 = 1e-6;
+
+pub type Triangle = [[f64; 2]; 3];
 
 pub struct Visibility {
-    pub polygon_pts: Vec<[f64; 2]>,
+    /// Everything lit, cut into triangles for drawing.
+    pub triangles: Vec<Triangle>,
     pub tiles: Vec<usize>,
 }
 
-pub fn line_of_sight(viewer_x: i32, viewer_y: i32, width: usize, height: usize, walls_polygon: &geo::MultiPolygon<f64>) -> Visibility {
-    let viewer = geo::Point::new(
-        (viewer_x as f64 + 0.5) * TILE_SIZE,
-        (viewer_y as f64 + 0.5) * TILE_SIZE,
-    );
-    let vis_poly = viewer.visibility(walls_polygon);
-
-    let mut tiles = Vec::new();
-    for y in 0..height {
-        for x in 0..width {
-            let xf = x as f64 * TILE_SIZE;
-            let yf = y as f64 * TILE_SIZE;
-            let tile_poly = polygon![
-                geo::Coordinate { x: xf, y: yf },
-                geo::Coordinate { x: xf + TILE_SIZE, y: yf },
-                geo::Coordinate { x: xf + TILE_SIZE , y: yf + TILE_SIZE },
-                geo::Coordinate { x: xf, y: yf + TILE_SIZE },
-            ];
-            if vis_poly.contains(&tile_poly) {
+/// Light spreading out from `apex`. Once it's bounced off a mirror, `apex` is where the light
+/// seems to come from in the mirror, and only what's past `window`, the lit stretch of that
+/// mirror, is really lit.
+struct Beam {
+    apex: Coordinate<f64>,
+    window: Option<Line<f64>>,
+    bounces: usize,
+}
+
+fn cross(a: Coordinate<f64>, b: Coordinate<f64>) -> f64 {
+    a.x * b.y - a.y * b.x
+}
+
+/// How far to the left of a line a point is, scaled by the line's length.
+fn side(line: &Line<f64>, point: Coordinate<f64>) -> f64 {
+    cross(line.delta(), point - line.start)
+}
+
+fn is_on_line(line: &Line<f64>, point: Coordinate<f64>) -> bool {
+    let length = line.delta().x.hypot(line.delta().y);
+    (side(line, point) / length).abs() < EPSILON
+}
+
+fn dot(a: Coordinate<f64>, b: Coordinate<f64>) -> f64 {
+    a.x * b.x + a.y * b.y
+}
+
+fn distance(a: Coordinate<f64>, b: Coordinate<f64>) -> f64 {
+    (b.x - a.x).hypot(b.y - a.y)
+}
+
+fn is_same_point(a: Coordinate<f64>, b: Coordinate<f64>) -> bool {
+    distance(a, b) <= f64::EPSILON
+}
+
+/// Which way the path from `a` to `b` has to turn to reach `c`: one way's `Less`, the other's
+/// `Greater`, and straight on is `Equal`.
+fn turn(a: Coordinate<f64>, b: Coordinate<f64>, c: Coordinate<f64>) -> Ordering {
+    let det = cross(b - a, c - a);
+    if det > 0. {
+        Ordering::Greater
+    } else if det < 0. {
+        Ordering::Less
+    } else {
+        Ordering::Equal
+    }
+}
+
+fn reflect(point: Coordinate<f64>, mirror: &Line<f64>) -> Coordinate<f64> {
+    let delta = mirror.delta();
+    let t = (point - mirror.start).x * delta.x + (point - mirror.start).y * delta.y;
+    let foot = mirror.start + delta * (t / (delta.x * delta.x + delta.y * delta.y));
+    foot * 2. - point
+}
+
+/// The part of a polygon on one side of a line: the left if `sign` is positive, the right if negative.
+fn clip(points: &[Coordinate<f64>], line: &Line<f64>, sign: f64) -> Vec<Coordinate<f64>> {
+    let mut clipped = Vec::new();
+    for (i, &p) in points.iter().enumerate() {
+        let q = points[(i + 1) % points.len()];
+        let (sp, sq) = (side(line, p) * sign, side(line, q) * sign);
+        if sp >= 0. { clipped.push(p); }
+        if (sp >= 0.) != (sq >= 0.) {
+            clipped.push(p + (q - p) * (sp / (sp - sq)));
+        }
+    }
+    clipped
+}
+
+/// The part of a line segment on one side of a line, as with `clip`, unless that's just a point.
+fn clip_segment(segment: &Line<f64>, line: &Line<f64>, sign: f64) -> Option<Line<f64>> {
+    let (sp, sq) = (side(line, segment.start) * sign, side(line, segment.end) * sign);
+    if sp <= 0. && sq <= 0. { return None; }
+    if sp >= 0. && sq >= 0. { return Some(*segment); }
+    let crossing = segment.start + segment.delta() * (sp / (sp - sq));
+    let clipped = if sp >= 0. { Line::new(segment.start, crossing) } else { Line::new(crossing, segment.end) };
+    let length = clipped.delta().x.hypot(clipped.delta().y);
+    if length < EPSILON { None } else { Some(clipped) }
+}
+
+/// The stretches of a mirror that run along a lit area's edge, which is to say the parts of it
+/// the light hits.
+fn lit_stretches(outline: &[Coordinate<f64>], mirror: &Line<f64>) -> Vec<Line<f64>> {
+    let delta = mirror.delta();
+    let length_squared = delta.x * delta.x + delta.y * delta.y;
+    let along = |point: Coordinate<f64>| ((point - mirror.start).x * delta.x + (point - mirror.start).y * delta.y) / length_squared;
+    let within = |t: f64| t > -EPSILON && t < 1. + EPSILON;
+    let mut spans = Vec::new();
+    for (i, &p) in outline.iter().enumerate() {
+        let q = outline[(i + 1) % outline.len()];
+        if !is_on_line(mirror, p) || !is_on_line(mirror, q) { continue; }
+        let (tp, tq) = (along(p), along(q));
+        if within(tp) && within(tq) && (tp - tq).abs() > EPSILON {
+            spans.push((tp.min(tq).max(0.), tp.max(tq).min(1.)));
+        }
+    }
+    spans.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+    let mut merged: Vec<(f64, f64)> = Vec::new();
+    for (start, end) in spans {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 + EPSILON => { last.1 = last.1.max(end); },
+            _ => merged.push((start, end)),
+        }
+    }
+    merged.into_iter()
+        .map(|(start, end)| Line::new(mirror.start + delta * start, mirror.start + delta * end))
+        .collect()
+}
+
+// Working out what a light can see is a sweep around it, done the way Gérald Lelong's
+// geo-visibility crate (ISC license) does it, which follows the C++ library trylock/visibility.
+// It used to come from that crate, but the crate also pulls in a C++ polygon clipper, which
+// needs libclang to build, for things this never asks it for. It's kept step for step, so the
+// light falls exactly where it did.
+
+/// Where a ray from `origin` heading through `toward` first meets a segment, if it does.
+fn ray_hit(origin: Coordinate<f64>, toward: Coordinate<f64>, segment: &Line<f64>) -> Option<Coordinate<f64>> {
+    let tolerance = 1e-4;
+    let direction = toward - origin;
+    let (a, b) = (segment.start, segment.end);
+    let (ao, ab) = (origin - a, b - a);
+    let det = cross(ab, direction);
+    if det.abs() < tolerance {
+        // Running alongside the segment: it's only hit if it's right in the ray's path.
+        if turn(a, b, origin) != Ordering::Equal { return None; }
+        let (along_a, along_b) = (dot(ao, direction), dot(origin - b, direction));
+        return if along_a > 0. && along_b > 0. {
+            None
+        } else if (along_a > 0.) != (along_b > 0.) {
+            Some(origin)
+        } else if along_a > along_b {
+            Some(a)
+        } else {
+            Some(b)
+        };
+    }
+    let u = cross(ao, direction) / det;
+    if !(0. ..=1.).contains(&u) { return None; }
+    let t = -cross(ab, ao) / det;
+    if t.abs() < tolerance || t > 0. { Some(origin + direction * t) } else { None }
+}
+
+/// Orders points by which way they are from `origin`, going around from the line straight up
+/// and down through it.
+fn by_angle(origin: Coordinate<f64>, a: Coordinate<f64>, b: Coordinate<f64>) -> Ordering {
+    let (a_left, b_left) = (a.x < origin.x, b.x < origin.x);
+    if a_left != b_left {
+        return if b_left { Ordering::Less } else { Ordering::Greater };
+    }
+    let is_in_line = |point: Coordinate<f64>| (point.x - origin.x).abs() <= f64::EPSILON;
+    if is_in_line(a) && is_in_line(b) {
+        let down = a.y >= origin.y || b.y >= origin.y;
+        return if (down && b.y < a.y) || (!down && a.y < b.y) { Ordering::Less } else { Ordering::Greater };
+    }
+    let det = cross(a - origin, b - origin);
+    if det.abs() <= f64::EPSILON {
+        if distance(a, origin) < distance(b, origin) { Ordering::Less } else { Ordering::Greater }
+    } else if det < 0. {
+        Ordering::Less
+    } else {
+        Ordering::Greater
+    }
+}
+
+/// An obstacle the sweep is passing over, ordered by how close it is to the light along the
+/// current ray. None of them ever run straight at the light.
+struct Swept {
+    origin: Coordinate<f64>,
+    line: Line<f64>,
+}
+
+impl Ord for Swept {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let origin = self.origin;
+        let (a, b) = (self.line.start, self.line.end);
+        let (c, d) = (other.line.start, other.line.end);
+        // Put any end the two share first in both.
+        let (a, b) = if is_same_point(b, c) || is_same_point(b, d) { (b, a) } else { (a, b) };
+        let (c, d) = if is_same_point(a, d) { (d, c) } else { (c, d) };
+        if is_same_point(a, c) {
+            return if is_same_point(b, d) {
+                Ordering::Equal
+            } else if turn(origin, a, d) != turn(origin, a, b) {
+                Ordering::Greater
+            } else if turn(a, b, d) != turn(a, b, origin) {
+                Ordering::Less
+            } else {
+                Ordering::Greater
+            };
+        }
+        let (cda, cdb) = (turn(c, d, a), turn(c, d, b));
+        if cda == Ordering::Equal && cdb == Ordering::Equal {
+            if distance(origin, a) < distance(origin, c) { Ordering::Less } else { Ordering::Greater }
+        } else if cda == cdb || cda == Ordering::Equal || cdb == Ordering::Equal {
+            // This one's all on one side of the other: nearer if that's the light's side.
+            let cdo = turn(c, d, origin);
+            if cdo == cda || cdo == cdb { Ordering::Less } else { Ordering::Greater }
+        } else if turn(a, b, origin) != turn(a, b, c) {
+            Ordering::Less
+        } else {
+            Ordering::Greater
+        }
+    }
+}
+
+impl PartialOrd for Swept {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Swept {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Swept {}
+
+/// The outline of everything a light at `origin` can see, with `obstacles` in the way.
+/// Nothing stops the light but the obstacles, so they'd better surround it.
+fn visible_outline(origin: Coordinate<f64>, obstacles: &[Line<f64>]) -> Vec<Coordinate<f64>> {
+    // Each obstacle's two ends, pointing away from wherever the sweep meets it first or last,
+    // and whether the sweep's starting to pass over it there.
+    let mut ends = Vec::with_capacity(obstacles.len() * 2);
+    let mut passing = BTreeSet::new();
+    for segment in obstacles {
+        let reversed = Line::new(segment.end, segment.start);
+        match turn(origin, segment.start, segment.end) {
+            Ordering::Equal => { continue; },
+            Ordering::Less => { ends.push((*segment, true)); ends.push((reversed, false)); },
+            Ordering::Greater => { ends.push((reversed, true)); ends.push((*segment, false)); },
+        }
+        // The sweep starts out already passing over anything that crosses the line it starts on.
+        let (a, b) = if segment.start.x > segment.end.x { (segment.end, segment.start) } else { (segment.start, segment.end) };
+        let crosses = (b.x - origin.x).abs() <= f64::EPSILON || (a.x < origin.x && origin.x < b.x);
+        if crosses && turn(a, b, origin) == Ordering::Less {
+            passing.insert(Swept { origin, line: *segment });
+        }
+    }
+    ends.sort_by(|(a, a_starts), (b, b_starts)| {
+        if is_same_point(a.start, b.start) {
+            // Leave one obstacle before getting onto the next.
+            if !a_starts && *b_starts { Ordering::Less } else { Ordering::Greater }
+        } else {
+            by_angle(origin, a.start, b.start)
+        }
+    });
+
+    let mut outline = Vec::new();
+    for (line, starts) in ends {
+        let point = line.start;
+        let swept = Swept { origin, line };
+        if !starts { passing.remove(&swept); }
+        match passing.iter().next() {
+            // Coming out from behind the nearest obstacle, or going behind it.
+            Some(nearest) if swept < *nearest => {
+                if let Some(hit) = ray_hit(origin, point, &nearest.line) {
+                    if starts {
+                        outline.extend([hit, point]);
+                    } else {
+                        outline.extend([point, hit]);
+                    }
+                }
+            },
+            Some(_) => (),
+            None => outline.push(point),
+        }
+        if starts { passing.insert(swept); }
+    }
+
+    // Drop any corners that are really just points along a straight edge.
+    let mut kept = 0;
+    for i in 0..outline.len() {
+        let prev = if kept == 0 { outline.len() - 1 } else { kept - 1 };
+        let next = (i + 1) % outline.len();
+        if turn(outline[prev], outline[i], outline[next]) != Ordering::Equal {
+            outline[kept] = outline[i];
+            kept += 1;
+        }
+    }
+    outline.truncate(kept);
+    // The sweep can come back around to where it began.
+    if outline.len() > 1 && outline.first() == outline.last() { outline.pop(); }
+    outline
+}
+
+fn to_point(coordinate: Coordinate<f64>) -> [f64; 2] {
+    [coordinate.x, coordinate.y]
+}
+
+impl Beam {
+    /// The outline of the lit area, and the lit area cut into triangles.
+    fn shine(&self, obstacles: &[Line<f64>], width: usize, height: usize) -> (Vec<Coordinate<f64>>, Vec<Triangle>) {
+        let window = match &self.window {
+            Some(window) => window,
+            None => {
+                let outline = visible_outline(self.apex, obstacles);
+                let triangles = (0..outline.len())
+                    .map(|i| [to_point(outline[i]), to_point(outline[(i + 1) % outline.len()]), to_point(self.apex)])
+                    .collect();
+                return (outline, triangles);
+            },
+        };
+
+        // The light only reaches the far side of the mirror from its reflection, so anything on
+        // the near side (the mirror included) can't get in its way. With nothing left there, box
+        // it all in instead.
+        let far_side = -side(window, self.apex).signum();
+        let mut segments: Vec<_> = obstacles.iter()
+            .filter_map(|segment| clip_segment(segment, window, far_side))
+            .collect();
+        let left = self.apex.x.min(0.) - TILE_SIZE;
+        let top = self.apex.y.min(0.) - TILE_SIZE;
+        let right = self.apex.x.max(width as f64 * TILE_SIZE) + TILE_SIZE;
+        let bottom = self.apex.y.max(height as f64 * TILE_SIZE) + TILE_SIZE;
+        let corners = [
+            Coordinate { x: left, y: top },
+            Coordinate { x: right, y: top },
+            Coordinate { x: right, y: bottom },
+            Coordinate { x: left, y: bottom },
+        ];
+        for i in 0..4 {
+            segments.push(Line::new(corners[i], corners[(i + 1) % 4]));
+        }
+
+        // Then keep only what's seen through the window, past the mirror.
+        let seen = visible_outline(self.apex, &segments);
+        let wedge = [
+            (Line::new(self.apex, window.start), side(&Line::new(self.apex, window.start), window.end).signum()),
+            (Line::new(self.apex, window.end), side(&Line::new(self.apex, window.end), window.start).signum()),
+            (*window, far_side),
+        ];
+        let through_window = |points: &[Coordinate<f64>]| {
+            wedge.iter().fold(points.to_vec(), |points, (line, sign)| clip(&points, line, *sign))
+        };
+        let outline = through_window(&seen);
+        let mut triangles = Vec::new();
+        for i in 0..seen.len() {
+            let piece = through_window(&[seen[i], seen[(i + 1) % seen.len()], self.apex]);
+            for j in 1..piece.len().saturating_sub(1) {
+                triangles.push([to_point(piece[0]), to_point(piece[j]), to_point(piece[j + 1])]);
+            }
+        }
+        (outline, triangles)
+    }
+}
+
+/// Everything lit by a light source in the middle of the given tile, bouncing off mirrors.
+pub fn line_of_sight(
+    viewer_x: i32,
+    viewer_y: i32,
+    width: usize,
+    height: usize,
+    obstacles: &[Line<f64>],
+    mirrors: &[Line<f64>],
+) -> Visibility {
+    let all_obstacles: Vec<_> = obstacles.iter().chain(mirrors).cloned().collect();
+    let mut lit = vec![false; width * height];
+    let mut triangles = Vec::new();
+    let mut beams = vec![Beam {
+        apex: Coordinate { x: (viewer_x as f64 + 0.5) * TILE_SIZE, y: (viewer_y as f64 + 0.5) * TILE_SIZE },
+        window: None,
+        bounces: 0,
+    }];
+    while let Some(beam) = beams.pop() {
+        let (outline, beam_triangles) = beam.shine(&all_obstacles, width, height);
+        triangles.extend(beam_triangles);
+        if outline.len() < 3 { continue; }
+
+        let lit_area = geo::Polygon::new(LineString(outline.clone()), vec![]);
+        for y in 0..height {
+            for x in 0..width {
                 let idx = x + y * width;
-                tiles.push(idx);
+                if lit[idx] { continue; }
+                let xf = x as f64 * TILE_SIZE;
+                let yf = y as f64 * TILE_SIZE;
+                let tile_poly = polygon![
+                    Coordinate { x: xf, y: yf },
+                    Coordinate { x: xf + TILE_SIZE, y: yf },
+                    Coordinate { x: xf + TILE_SIZE , y: yf + TILE_SIZE },
+                    Coordinate { x: xf, y: yf + TILE_SIZE },
+                ];
+                lit[idx] = lit_area.contains(&tile_poly);
+            }
+        }
+
+        if beam.bounces == MAX_BOUNCES { continue; }
+        for mirror in mirrors {
+            // Light can't bounce off the mirror it just came off, or anything in line with it.
+            if let Some(window) = &beam.window {
+                if is_on_line(window, mirror.start) && is_on_line(window, mirror.end) { continue; }
+            }
+            for stretch in lit_stretches(&outline, mirror) {
+                beams.push(Beam {
+                    apex: reflect(beam.apex, mirror),
+                    window: Some(stretch),
+                    bounces: beam.bounces + 1,
+                });
             }
         }
     }
 
-    // just give me the floats. for gods sake please just give me the floats.
-    let (line, _) = vis_poly.into_inner();
-    let polygon_pts = line.into_points().into_iter().map(|p| [p.x(), p.y()]).collect();
-    Visibility { polygon_pts, tiles }
+    let tiles = (0..lit.len()).filter(|&idx| lit[idx]).collect();
+    Visibility { triangles, tiles }
 }
@@ -0,0 +1,231 @@
+use std::collections::HashSet;
+use std::fmt;
+use crate::color::Color;
+use crate::direction::Direction;
+use crate::puzzle::{Kind, Puzzle};
+
+// Design checks for levels that parse fine but probably don't play the way their author meant.
+// None of these try to solve the level (that's `solver`'s job); they only look at what the
+// player could possibly reach and which lights could possibly come on.
+
+/// Something suspicious about a level. Lines and columns count from 1, header included,
+/// the same as `LevelError`.
+#[derive(Debug, PartialEq)]
+pub enum Lint {
+    /// A switch or pressure plate for a color with no bulbs or lamps of any of its primaries,
+    /// which plunges the room into darkness.
+    SwitchWithoutBulb { line: usize, column: usize, color: Color },
+    /// A block no light it could ever sit in will make intangible.
+    NeverIntangible { line: usize, column: usize, color: Color },
+    /// Floor the player can never get to, however the pieces are moved.
+    UnreachableRegion { line: usize, column: usize, size: usize },
+    /// An exit the player can never get to, however the pieces are moved.
+    UnreachableExit { line: usize, column: usize },
+    /// More goals of a color than there are blocks to fill them, so the exits never open.
+    /// Reported at the first goal of the color.
+    UnfillableGoals { line: usize, column: usize, color: Color },
+}
+
+impl Lint {
+    /// Whether the level is definitely broken, rather than just odd.
+    pub fn is_error(&self) -> bool {
+        matches!(self, Lint::UnreachableExit { .. } | Lint::UnfillableGoals { .. })
+    }
+}
+
+fn color_name(color: &Color) -> &'static str {
+    match color {
+        Color::Gray => "gray",
+        Color::Red => "red",
+        Color::Green => "green",
+        Color::Blue => "blue",
+        Color::Yellow => "yellow",
+        Color::Cyan => "cyan",
+        Color::Magenta => "magenta",
+        Color::White => "white",
+    }
+}
+
+/// Why a block of this color never turns intangible, given that it doesn't.
+fn never_lit_reason(color: &Color) -> String {
+    let primaries: Vec<_> = color.primaries().map(|primary| color_name(&primary)).collect();
+    match primaries.as_slice() {
+        [primary] => format!("no {} light ever reaches it", primary),
+        _ => format!("{} light never reach it together", primaries.join(" and ")),
+    }
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Lint::SwitchWithoutBulb { line, column, color } => {
+                let primaries: Vec<_> = color.primaries().map(|primary| color_name(&primary)).collect();
+                write!(f, "line {}, column {}: {} switch, but there are no {} bulbs",
+                    line, column, color_name(color), primaries.join(" or "))
+            },
+            Lint::NeverIntangible { line, column, color } => {
+                write!(f, "line {}, column {}: {} block can never turn intangible: {}",
+                    line, column, color_name(color), never_lit_reason(color))
+            },
+            Lint::UnreachableRegion { line, column, size } => {
+                write!(f, "line {}, column {}: {} floor tiles the player can never reach", line, column, size)
+            },
+            Lint::UnreachableExit { line, column } => {
+                write!(f, "line {}, column {}: exit is walled off from the player", line, column)
+            },
+            Lint::UnfillableGoals { line, column, color: Color::Gray } => {
+                write!(f, "line {}, column {}: more goals than there are blocks to fill them", line, column)
+            },
+            Lint::UnfillableGoals { line, column, color } => {
+                write!(f, "line {}, column {}: more {} goals than there are {} blocks to fill them",
+                    line, column, color_name(color), color_name(color))
+            },
+        }
+    }
+}
+
+fn position(x: i32, y: i32) -> (usize, usize) {
+    (y as usize + 2, x as usize + 1)
+}
+
+/// Every tile reachable from the given one, supposing every block is pushed out of the way
+/// and all the water filled in. Lightbulbs never move and pits never fill, so they're as good
+/// as walls.
+fn region(puzzle: &Puzzle, start: (i32, i32)) -> HashSet<(i32, i32)> {
+    let fixtures: HashSet<_> = puzzle.pieces.iter()
+        .filter(|p| is_fixture(&p.kind))
+        .map(|p| (p.x, p.y))
+        .collect();
+    let mut seen = HashSet::new();
+    let mut stack = vec![start];
+    seen.insert(start);
+    while let Some((x, y)) = stack.pop() {
+        for direction in Direction::ALL.iter() {
+            let next = direction.from(x, y);
+            if !puzzle.tile_is_passable(next.0, next.1) || fixtures.contains(&next) { continue; }
+            if seen.insert(next) { stack.push(next); }
+        }
+    }
+    seen
+}
+
+fn is_fixture(kind: &Kind) -> bool {
+    matches!(kind, Kind::Lightbulb(_) | Kind::Pit)
+}
+
+pub fn lint(puzzle: &Puzzle) -> Vec<Lint> {
+    let mut lints = Vec::new();
+    let reachable = region(puzzle, puzzle.player);
+
+    // Every light color the room could ever be: the one it starts with, plus any switch or plate the
+    // player can get to, or push a block onto.
+    let mut light_colors = vec![puzzle.light_color.clone()];
+    for piece in &puzzle.pieces {
+        if let Kind::LightSwitch(color) | Kind::PressurePlate(color) = &piece.kind {
+            if reachable.contains(&(piece.x, piece.y)) && !light_colors.contains(color) {
+                light_colors.push(color.clone());
+            }
+        }
+    }
+    let lamp_colors: Vec<_> = puzzle.pieces.iter()
+        .filter_map(|p| match &p.kind {
+            Kind::Lamp(color) => Some(color.clone()),
+            _ => None,
+        })
+        .collect();
+    let paints: Vec<_> = puzzle.pieces.iter()
+        .filter(|p| matches!(p.kind, Kind::Paint(_)))
+        .map(|p| (p.x, p.y))
+        .collect();
+
+    // Shadows move with the gray blocks casting them, so be generous and leave them out.
+    let mut unshaded = puzzle.clone();
+    unshaded.pieces.retain(|p| !p.kind.is_opaque());
+    unshaded.relight();
+    let lit_puzzles: Vec<_> = light_colors.into_iter()
+        .map(|color| {
+            let mut lit = unshaded.clone();
+            lit.light_color = color;
+            lit
+        })
+        .collect();
+
+    for piece in &puzzle.pieces {
+        let (line, column) = position(piece.x, piece.y);
+        match &piece.kind {
+            // Turning the lights off needs no bulbs at all.
+            Kind::LightSwitch(Color::Gray) | Kind::PressurePlate(Color::Gray) => (),
+            Kind::LightSwitch(color) | Kind::PressurePlate(color) => {
+                let has_bulb = puzzle.pieces.iter()
+                    .any(|p| p.kind.light_color().is_some_and(|light| color.contains(light)));
+                if !has_bulb {
+                    lints.push(Lint::SwitchWithoutBulb { line, column, color: color.clone() });
+                }
+            },
+            // Gray blocks are solid on purpose, and white ones are never solid at all.
+            Kind::Block(Color::Gray | Color::White) => (),
+            Kind::Block(color) => {
+                let tiles = region(puzzle, (piece.x, piece.y));
+                // Paint could make it any color at all.
+                if paints.iter().any(|paint| tiles.contains(paint)) { continue; }
+                // A lamp could be pushed anywhere, so be generous about its light too.
+                let ever_lit = lit_puzzles.iter().any(|lit| tiles.iter().any(|&(x, y)| {
+                    color.primaries().all(|primary| {
+                        lit.light_color.contains(&primary)
+                            && (lamp_colors.contains(&primary) || lit.tile_in_light(x, y, &primary))
+                    })
+                }));
+                if !ever_lit {
+                    lints.push(Lint::NeverIntangible { line, column, color: color.clone() });
+                }
+            },
+            Kind::Exit => {
+                if !reachable.contains(&(piece.x, piece.y)) {
+                    lints.push(Lint::UnreachableExit { line, column });
+                }
+            },
+            Kind::Lightbulb(_) | Kind::Lamp(_) | Kind::Mirror(_) | Kind::Paint(_) | Kind::Pit | Kind::Water => (),
+        }
+    }
+
+    // Gray goals take any block, so between them all the goals need a block each; colored ones
+    // need that many of their own color too, unless there's paint to make more.
+    let blocks: Vec<_> = puzzle.pieces.iter()
+        .filter_map(|p| match &p.kind {
+            Kind::Block(color) => Some(color),
+            _ => None,
+        })
+        .collect();
+    let mut goal_colors: Vec<&Color> = Vec::new();
+    for (x, y, color) in puzzle.room.goals() {
+        if goal_colors.contains(&color) { continue; }
+        goal_colors.push(color);
+        let (needed, available) = match color {
+            Color::Gray => (puzzle.room.goals().count(), blocks.len()),
+            _ if puzzle.pieces.iter().any(|p| p.kind == Kind::Paint(color.clone())) => continue,
+            _ => (
+                puzzle.room.goals().filter(|&(_, _, goal)| goal == color).count(),
+                blocks.iter().filter(|&&block| block == color).count(),
+            ),
+        };
+        if needed > available {
+            let (line, column) = position(x, y);
+            lints.push(Lint::UnfillableGoals { line, column, color: color.clone() });
+        }
+    }
+
+    let mut seen = reachable;
+    let width = puzzle.room.width() as i32;
+    let height = puzzle.room.height() as i32;
+    for y in 0..height {
+        for x in 0..width {
+            if seen.contains(&(x, y)) || !puzzle.tile_is_passable(x, y) { continue; }
+            if puzzle.piece_at(x, y).is_some_and(|p| is_fixture(&p.kind)) { continue; }
+            let unreachable = region(puzzle, (x, y));
+            let (line, column) = position(x, y);
+            lints.push(Lint::UnreachableRegion { line, column, size: unreachable.len() });
+            seen.extend(unreachable);
+        }
+    }
+    lints
+}
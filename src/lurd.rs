@@ -0,0 +1,129 @@
+use std::fmt;
+use crate::direction::Direction;
+
+// LURD notation, the usual way of writing down Sokoban solutions: a letter per move, for the
+// direction it went (Left, Up, Right, Down), in uppercase if the move pushed something. A count
+// before a letter repeats it (`3r` for `rrr`), and whitespace is ignored, so solutions can be
+// wrapped or pasted from a chat.
+
+/// The most moves a solution can spell out. Far more than any level needs, but small enough that
+/// a stray count in pasted text can't run the game out of memory.
+pub const MAX_MOVES: usize = 100_000;
+
+/// Why some text couldn't be read as moves. Columns count from 1.
+#[derive(Debug, PartialEq)]
+pub enum LurdError {
+    UnknownMove { column: usize, chr: char },
+    TrailingCount,
+    /// More than `MAX_MOVES` moves, counting repeats.
+    TooLong,
+}
+
+impl fmt::Display for LurdError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LurdError::UnknownMove { column, chr } => {
+                write!(f, "column {}: {:?} isn't a move (expected one of l, u, r, d)", column, chr)
+            },
+            LurdError::TrailingCount => write!(f, "ends with a count, but no move to repeat"),
+            LurdError::TooLong => write!(f, "more than {} moves", MAX_MOVES),
+        }
+    }
+}
+
+impl std::error::Error for LurdError {}
+
+/// The letter for a move, capitalized if it pushed something.
+pub fn letter(direction: &Direction, push: bool) -> char {
+    let letter = match direction {
+        Direction::West => 'l',
+        Direction::North => 'u',
+        Direction::East => 'r',
+        Direction::South => 'd',
+    };
+    if push { letter.to_ascii_uppercase() } else { letter }
+}
+
+/// Read moves back out of LURD. Which ones were pushes doesn't matter for playing them back,
+/// so the case is ignored.
+pub fn parse(text: &str) -> Result<Vec<Direction>, LurdError> {
+    let mut moves = Vec::new();
+    let mut count: Option<usize> = None;
+    for (idx, chr) in text.chars().enumerate() {
+        let direction = match chr.to_ascii_lowercase() {
+            'l' => Direction::West,
+            'u' => Direction::North,
+            'r' => Direction::East,
+            'd' => Direction::South,
+            digit @ '0'..='9' => {
+                let digit = digit.to_digit(10).unwrap_or(0) as usize;
+                let more = count.unwrap_or(0).checked_mul(10).and_then(|count| count.checked_add(digit));
+                count = Some(more.filter(|&count| count <= MAX_MOVES).ok_or(LurdError::TooLong)?);
+                continue;
+            },
+            _ if chr.is_whitespace() => continue,
+            _ => { return Err(LurdError::UnknownMove { column: idx + 1, chr }); },
+        };
+        let repeat = count.take().unwrap_or(1);
+        if moves.len() + repeat > MAX_MOVES { return Err(LurdError::TooLong); }
+        for _ in 0..repeat {
+            moves.push(direction.clone());
+        }
+    }
+    if count.is_some() { return Err(LurdError::TrailingCount); }
+    Ok(moves)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::room::Room;
+    use Direction::*;
+
+    #[test]
+    fn reads_moves_and_pushes_alike() {
+        assert_eq!(parse("lUrD"), Ok(vec![West, North, East, South]));
+    }
+
+    #[test]
+    fn skips_whitespace_and_expands_counts() {
+        assert_eq!(parse("3r\n u 2L"), Ok(vec![East, East, East, North, West, West]));
+        assert_eq!(parse("12d").map(|moves| moves.len()), Ok(12));
+    }
+
+    #[test]
+    fn rejects_anything_else() {
+        assert_eq!(parse("lux"), Err(LurdError::UnknownMove { column: 3, chr: 'x' }));
+        assert_eq!(parse("lu3"), Err(LurdError::TrailingCount));
+    }
+
+    #[test]
+    fn rejects_huge_counts_without_expanding_them() {
+        assert_eq!(parse("99999999999r"), Err(LurdError::TooLong));
+        assert_eq!(parse("999999999999999999999999r"), Err(LurdError::TooLong));
+        assert_eq!(parse("60000r60000l"), Err(LurdError::TooLong));
+        assert_eq!(parse(&"r".repeat(MAX_MOVES)).map(|moves| moves.len()), Ok(MAX_MOVES));
+    }
+
+    #[test]
+    fn only_pushes_the_player_follows_are_uppercase() {
+        let letters = |level: &[u8]| {
+            let mut puzzle = Room::parse(level).unwrap();
+            (0..2).map(|_| letter(&East, puzzle.step(&East).is_push())).collect::<String>()
+        };
+        assert_eq!(letters(b"W\n######\n#ak..#\n######\n"), "RR");
+        // Filling in water moves a block, but the player stays put, so it's no push to anyone else.
+        assert_eq!(letters(b"W\n######\n#ak~.#\n######\n"), "rr");
+    }
+
+    #[test]
+    fn letters_round_trip() {
+        for direction in Direction::ALL.iter() {
+            for &push in &[false, true] {
+                let letter = letter(direction, push);
+                assert_eq!(letter.is_ascii_uppercase(), push);
+                assert_eq!(parse(&letter.to_string()), Ok(vec![direction.clone()]));
+            }
+        }
+    }
+}
@@ -1,16 +1,44 @@
 use piston_window::*;
 use sdl2_window::Sdl2Window;
 
+use ld49::{color, direction, lighting, line_of_sight, lurd, pack, puzzle, room, solver};
+
 mod app;
-mod color;
 mod entity;
-mod line_of_sight;
-mod room;
+mod save;
 mod view;
 
 fn main() {
     use opengl_graphics::GlGraphics;
 
+    // Optionally play a level pack from disk: `cargo run --release -- path/to/pack`
+    // or, with `--solve` first, print par counts for it instead of playing.
+    // `--edit path/to/level.skb` opens the level editor instead.
+    let mut args: Vec<_> = std::env::args_os().skip(1).collect();
+    let solve = args.first().is_some_and(|arg| arg == "--solve");
+    if solve { args.remove(0); }
+    let edit = args.first().is_some_and(|arg| arg == "--edit");
+    if edit { args.remove(0); }
+    let edit_path = if edit {
+        Some(args.pop().unwrap_or_else(|| {
+            eprintln!("--edit needs a level file to edit");
+            std::process::exit(2);
+        }))
+    } else {
+        None
+    };
+    let pack = match args.first() {
+        Some(dir) => pack::Pack::load(dir.as_ref()).unwrap_or_else(|e| {
+            eprintln!("Couldn't load level pack, falling back to the built-in levels: {}", e);
+            pack::Pack::builtin()
+        }),
+        None => pack::Pack::builtin(),
+    };
+    if solve {
+        print_pars(&pack);
+        return;
+    }
+
     let (width, height) = (800, 800);
 
     let mut window: PistonWindow<Sdl2Window> =
@@ -18,11 +46,23 @@ fn main() {
             .build()
             .unwrap_or_else(|e| { panic!("Failed to build PistonWindow: {}", e) });
 
-    let mut app = app::App::new();
+    let mut app = app::App::new(pack, save::Save::load());
+    if let Some(path) = edit_path {
+        let editor = view::EditorView::open(path.into()).unwrap_or_else(|e| {
+            eprintln!("Couldn't open level for editing: {}", e);
+            std::process::exit(1);
+        });
+        app.open_editor(editor);
+    }
 
     let mut gl = GlGraphics::new(OpenGL::V3_2);
 
     while let Some(e) = window.next() {
+        let title = app.title();
+        if title != window.get_title() {
+            window.set_title(title);
+        }
+
         if let Some(ref args) = e.render_args() {
             app.render(args, &mut gl);
         }
@@ -31,6 +71,22 @@ fn main() {
            app.update(args);
         }
 
+        // The clipboard belongs to the window, so requests for it come all the way back out here.
+        match app.take_clipboard_request() {
+            Some(app::ClipboardRequest::Copy(text)) => {
+                if let Err(e) = window.window.video_subsystem.clipboard().set_clipboard_text(&text) {
+                    eprintln!("Couldn't copy to the clipboard ({}), so here it is: {}", e, text);
+                }
+            },
+            Some(app::ClipboardRequest::Paste) => {
+                match window.window.video_subsystem.clipboard().clipboard_text() {
+                    Ok(text) => app.paste(&text),
+                    Err(e) => eprintln!("Couldn't read the clipboard: {}", e),
+                }
+            },
+            None => (),
+        }
+
         if let Some(ref args) = e.press_args() {
             app.key_press(args);
         }
@@ -40,3 +96,21 @@ fn main() {
         }
     }
 }
+
+fn print_pars(pack: &pack::Pack) {
+    for (level_id, level) in pack.levels.iter().enumerate() {
+        let puzzle = pack.puzzle(level_id);
+        match solver::solve(&puzzle, solver::DEFAULT_LIMIT) {
+            solver::Solution::Solved(moves) => {
+                let mut replay = puzzle;
+                for direction in &moves { replay.step(direction); }
+                println!("{} ({}): par {} moves, {} pushes",
+                    level.name, level.file, replay.stats.moves, replay.stats.pushes);
+            },
+            solver::Solution::Unsolvable => println!("{} ({}): unsolvable", level.name, level.file),
+            solver::Solution::GaveUp { explored } => {
+                println!("{} ({}): gave up after {} positions", level.name, level.file, explored);
+            },
+        }
+    }
+}
@@ -0,0 +1,153 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use crate::puzzle::Puzzle;
+use crate::room::{LevelError, Room};
+
+// A level pack is a directory of .skb files, optionally with a manifest.txt giving their order and names.
+// Without a manifest, every .skb file in the directory is played in filename order.
+
+const MANIFEST: &str = "manifest.txt";
+const BUILTIN_MANIFEST: &str = include_str!("../bin/levels/manifest.txt");
+const BUILTIN_LEVELS: [(&str, &[u8]); 8] = [
+    ("level01.skb", include_bytes!("../bin/levels/level01.skb")),
+    ("level02.skb", include_bytes!("../bin/levels/level02.skb")),
+    ("level03.skb", include_bytes!("../bin/levels/level03.skb")),
+    ("level04.skb", include_bytes!("../bin/levels/level04.skb")),
+    ("level05.skb", include_bytes!("../bin/levels/level05.skb")),
+    ("level06.skb", include_bytes!("../bin/levels/level06.skb")),
+    ("level07.skb", include_bytes!("../bin/levels/level07.skb")),
+    ("level08.skb", include_bytes!("../bin/levels/level08.skb")),
+];
+
+#[derive(Debug)]
+pub enum PackError {
+    Io(PathBuf, io::Error),
+    Manifest { line: usize, message: String },
+    Level { file: String, error: LevelError },
+    Empty,
+}
+
+impl fmt::Display for PackError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PackError::Io(path, error) => write!(f, "{}: {}", path.display(), error),
+            PackError::Manifest { line, message } => write!(f, "{}, line {}: {}", MANIFEST, line, message),
+            PackError::Level { file, error } => write!(f, "{}, {}", file, error),
+            PackError::Empty => write!(f, "pack has no levels"),
+        }
+    }
+}
+
+impl std::error::Error for PackError {}
+
+pub struct Level {
+    pub file: String,
+    pub name: String,
+    source: Vec<u8>,
+}
+
+pub struct Pack {
+    pub title: String,
+    pub author: Option<String>,
+    pub levels: Vec<Level>,
+}
+
+struct Manifest {
+    title: Option<String>,
+    author: Option<String>,
+    levels: Vec<(String, Option<String>)>,
+}
+
+fn parse_manifest(text: &str) -> Result<Manifest, PackError> {
+    let mut manifest = Manifest { title: None, author: None, levels: Vec::new() };
+    for (idx, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') { continue; }
+        let mut words = line.splitn(2, char::is_whitespace);
+        let key = words.next().unwrap_or_default();
+        let value = words.next().map(str::trim).unwrap_or_default();
+        let error = |message: &str| PackError::Manifest { line: idx + 1, message: message.to_string() };
+        match key {
+            "title" => { manifest.title = Some(value.to_string()); },
+            "author" => { manifest.author = Some(value.to_string()); },
+            "level" => {
+                let mut words = value.splitn(2, char::is_whitespace);
+                let file = words.next().filter(|file| !file.is_empty())
+                    .ok_or_else(|| error("`level` needs a file name"))?;
+                let name = words.next().map(|name| name.trim().to_string());
+                manifest.levels.push((file.to_string(), name));
+            },
+            _ => { return Err(error(&format!("unknown key {:?}", key))); },
+        }
+    }
+    Ok(manifest)
+}
+
+impl Pack {
+    /// The levels that ship with the game.
+    pub fn builtin() -> Self {
+        let manifest = parse_manifest(BUILTIN_MANIFEST)
+            .unwrap_or_else(|e| panic!("built-in manifest is malformed: {}", e));
+        let sources = manifest.levels.iter().map(|(file, _)| {
+            BUILTIN_LEVELS.iter()
+                .find(|(name, _)| name == file)
+                .map(|(_, bytes)| bytes.to_vec())
+                .unwrap_or_else(|| panic!("built-in manifest lists unknown level {}", file))
+        }).collect();
+        Pack::assemble(manifest, sources)
+            .unwrap_or_else(|e| panic!("built-in pack is malformed: {}", e))
+    }
+
+    pub fn load(dir: &Path) -> Result<Self, PackError> {
+        let read = |path: PathBuf| fs::read(&path).map_err(|e| PackError::Io(path, e));
+        let manifest_path = dir.join(MANIFEST);
+        let manifest = if manifest_path.exists() {
+            let text = read(manifest_path)?;
+            parse_manifest(&String::from_utf8_lossy(&text))?
+        } else {
+            let entries = fs::read_dir(dir).map_err(|e| PackError::Io(dir.to_path_buf(), e))?;
+            let mut files = Vec::new();
+            for entry in entries {
+                let path = entry.map_err(|e| PackError::Io(dir.to_path_buf(), e))?.path();
+                if path.extension().is_some_and(|ext| ext == "skb") {
+                    if let Some(file) = path.file_name() {
+                        files.push((file.to_string_lossy().into_owned(), None));
+                    }
+                }
+            }
+            files.sort();
+            let title = dir.file_name().map(|name| name.to_string_lossy().into_owned());
+            Manifest { title, author: None, levels: files }
+        };
+        let sources = manifest.levels.iter()
+            .map(|(file, _)| read(dir.join(file)))
+            .collect::<Result<_, _>>()?;
+        Pack::assemble(manifest, sources)
+    }
+
+    /// Pair up manifest entries with their level files, checking every level parses up front
+    /// so a broken pack is reported when it loads rather than when someone reaches the bad level.
+    fn assemble(manifest: Manifest, sources: Vec<Vec<u8>>) -> Result<Self, PackError> {
+        let mut levels = Vec::new();
+        for (idx, ((file, name), source)) in manifest.levels.into_iter().zip(sources).enumerate() {
+            if let Err(error) = Room::from_file(&source) {
+                return Err(PackError::Level { file, error });
+            }
+            let name = name.unwrap_or_else(|| format!("Level {}", idx + 1));
+            levels.push(Level { file, name, source });
+        }
+        if levels.is_empty() { return Err(PackError::Empty); }
+        Ok(Pack {
+            title: manifest.title.unwrap_or_else(|| "Untitled".to_string()),
+            author: manifest.author,
+            levels,
+        })
+    }
+
+    pub fn puzzle(&self, level_id: usize) -> Puzzle {
+        Room::from_file(&self.levels[level_id].source)
+            .unwrap_or_else(|e| panic!("{} stopped parsing after the pack loaded: {}", self.levels[level_id].file, e))
+    }
+}
@@ -0,0 +1,729 @@
+use std::collections::{HashSet, VecDeque};
+use std::rc::Rc;
+use crate::color::Color;
+use crate::direction::Direction;
+use crate::lighting::Lighting;
+use crate::room::{Room, Tile};
+
+// The rules of the game, with no opinions about how any of it looks.
+// Views hold a `Puzzle`, call `step`, and animate whatever the `Outcome` says happened.
+
+/// Something a step did besides walk the player and push pieces along.
+#[derive(Debug)]
+pub enum GameAction {
+    ColorChange(Color),
+    Win,
+    /// A piece was taken out of the puzzle. This is its index from before the step; once the
+    /// step is over, the pieces after it have all moved down.
+    Remove(usize),
+    /// A block took on a new color.
+    Recolor(usize, Color),
+    /// A block turned solid around the player, and that's the end of them.
+    Crush,
+    /// A block turned solid around the player, and threw them out onto this tile.
+    Eject(i32, i32),
+}
+
+/// One thing that happens when a pushed piece runs into another.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Effect {
+    /// Everything being pushed moves along, the piece at the front onto the other one's tile.
+    Enter,
+    /// The piece at the front of the push is taken away.
+    RemovePushed,
+    /// The piece it ran into is taken away.
+    RemoveOther,
+    /// The light switches to the other piece's color.
+    Switch,
+    /// The other piece is a pressure plate, and it's held down.
+    Press,
+    /// The piece at the front of the push takes on the other one's color.
+    Paint,
+    /// The other piece's tile turns into a sunken block, for good.
+    Fill,
+}
+
+/// What happens, in order, when a pushed piece runs into another one.
+/// Anything not listed here stops the push.
+fn interaction(pushed: &Kind, other: &Kind) -> &'static [Effect] {
+    use Effect::*;
+    match (pushed, other) {
+        // The block fills the water in, and both are gone, leaving floor behind.
+        (Kind::Block(_), Kind::Water) => &[RemovePushed, RemoveOther, Fill],
+        // The block drops out of sight, and the pit's as deep as ever.
+        (Kind::Block(_), Kind::Pit) => &[Enter, RemovePushed],
+        (Kind::Block(_), Kind::LightSwitch(_)) => &[Enter, Switch],
+        (Kind::Block(_), Kind::PressurePlate(_)) => &[Enter, Press],
+        (Kind::Block(_), Kind::Paint(_)) => &[Enter, Paint],
+        // Mirrors and lamps slide like blocks, but stop at any piece in their way, even a flat one.
+        _ => &[],
+    }
+}
+
+/// Which way a mirror runs across its tile, as drawn with a slash.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Slant {
+    /// `/`, from the bottom left corner to the top right.
+    Forward,
+    /// `\`, from the top left corner to the bottom right.
+    Back,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Kind {
+    Block(Color),
+    Exit,
+    Lightbulb(Color),
+    /// A lightbulb that can be pushed around.
+    Lamp(Color),
+    LightSwitch(Color),
+    Mirror(Slant),
+    /// Paint that colors any block pushed onto it.
+    Paint(Color),
+    /// A hole that swallows blocks without ever filling up.
+    Pit,
+    /// A switch that only holds while a block sits on it, and puts the light back when it's gone.
+    PressurePlate(Color),
+    Water,
+}
+
+impl Kind {
+    /// Whether the piece casts a shadow. Only gray blocks do. Everything else lets light by:
+    /// - A colored block vanishes in its own light, so if it blocked that light it would put
+    ///   itself in shadow and come straight back. Other colors of light pass through it too,
+    ///   so a block's shadow never depends on which light is on.
+    /// - Mirrors don't stop light, they turn it. The reflective face is traced on its own.
+    /// - A lamp sits in the middle of its own light. If it stopped light it would have to stop
+    ///   its own, so it stops none, not even other bulbs'.
+    /// - The rest lie flat on the floor, or are the floor.
+    pub fn is_opaque(&self) -> bool {
+        matches!(self, Kind::Block(Color::Gray))
+    }
+
+    /// Whether the piece lies flat on the floor, so a block can be pushed on top of it.
+    pub fn is_flat(&self) -> bool {
+        matches!(self, Kind::LightSwitch(_) | Kind::Paint(_) | Kind::PressurePlate(_))
+    }
+
+    /// Whether moving the piece can change where the light falls.
+    pub fn affects_light(&self) -> bool {
+        self.is_opaque() || self.light_color().is_some() || matches!(self, Kind::Mirror(_))
+    }
+
+    /// The color of light the piece gives off, if it's a light source.
+    pub fn light_color(&self) -> Option<&Color> {
+        match self {
+            Kind::Lightbulb(color) | Kind::Lamp(color) => Some(color),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Piece {
+    pub x: i32,
+    pub y: i32,
+    pub kind: Kind,
+}
+
+/// What happens when the light changes and leaves the player inside a block that's turned solid.
+/// Walking through a lit block onto a switch underneath it is the only way that can happen.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Overlap {
+    #[default]
+    /// `forbid`: the switch won't work with the player in the way, so they can't step onto it.
+    Forbid,
+    /// `crush`: the player is crushed, and has to undo or start over.
+    Crush,
+    /// `eject`: the player is thrown out onto the nearest empty tile.
+    Eject,
+}
+
+/// Rules a level can turn on in its header, after the starting light color.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Rules {
+    /// `chain`: pushing a block shoves every solid block lined up in front of it along too.
+    pub chain_push: bool,
+    pub overlap: Overlap,
+}
+
+/// How much effort a run through a level has taken so far.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Stats {
+    pub moves: usize,
+    pub pushes: usize,
+}
+
+/// Everything a single `Puzzle::step` did.
+#[derive(Debug, Default)]
+pub struct Outcome {
+    /// The player moved onto the next tile.
+    pub walked: bool,
+    /// The indices of the pieces the player shoved ahead of them, nearest first.
+    pub pushed: Vec<usize>,
+    /// Everything else the step did, in the order it happened.
+    pub actions: Vec<GameAction>,
+}
+
+impl Outcome {
+    /// Whether the step changed anything at all.
+    pub fn is_move(&self) -> bool {
+        self.walked || !self.actions.is_empty()
+    }
+
+    /// Whether any piece was moved, taken away, or changed.
+    pub fn is_rearranged(&self) -> bool {
+        !self.pushed.is_empty()
+            || self.actions.iter().any(|action| matches!(action, GameAction::Remove(_) | GameAction::Recolor(..)))
+    }
+
+    /// Whether the player shoved something ahead of them and followed it: a push, as plain
+    /// Sokoban (and LURD) counts them.
+    pub fn is_push(&self) -> bool {
+        self.walked && !self.pushed.is_empty()
+    }
+
+    /// The indices of the pieces taken away, from before the step.
+    pub fn removed(&self) -> impl Iterator<Item = usize> + '_ {
+        self.actions.iter().filter_map(|action| match action {
+            GameAction::Remove(idx) => Some(*idx),
+            _ => None,
+        })
+    }
+
+    pub fn is_win(&self) -> bool {
+        self.actions.iter().any(|action| matches!(action, GameAction::Win))
+    }
+}
+
+/// Everything about a puzzle that can change from move to move, for telling positions apart.
+/// The room's tiles are left out: water only turns into floor when it's taken away, so the
+/// pieces already tell which has been filled in.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct StateKey {
+    player: (i32, i32),
+    pieces: Vec<Piece>,
+    light_color: Color,
+    plates: Vec<((i32, i32), Color)>,
+    crushed: bool,
+}
+
+#[derive(Clone)]
+pub struct Puzzle {
+    // Copies of the puzzle share the room, until one of them fills in some water and needs its own.
+    pub room: Rc<Room>,
+    pub player: (i32, i32),
+    pub pieces: Vec<Piece>,
+    /// Every light channel that's currently on, mixed together.
+    pub light_color: Color,
+    pub rules: Rules,
+    pub stats: Stats,
+    /// The light each weighed-down pressure plate replaced, by where the plate is.
+    pub plates: Vec<((i32, i32), Color)>,
+    /// Whether a block has turned solid around the player, leaving them unable to go on.
+    pub crushed: bool,
+    // Where the light reaches, given how the pieces are arranged. Kept up to date by `relight`.
+    lighting: Rc<Lighting>,
+}
+
+impl Puzzle {
+    pub fn new(room: Room, player: (i32, i32), pieces: Vec<Piece>, light_color: Color, rules: Rules) -> Self {
+        let lighting = room.lighting(&pieces);
+        Self {
+            room: Rc::new(room),
+            player,
+            pieces,
+            light_color,
+            rules,
+            stats: Stats::default(),
+            plates: Vec::new(),
+            crushed: false,
+            lighting,
+        }
+    }
+
+    pub fn lighting(&self) -> &Lighting {
+        &self.lighting
+    }
+
+    /// Work out the light and shadows again, after the pieces have been moved around.
+    pub fn relight(&mut self) {
+        self.lighting = self.room.lighting(&self.pieces);
+    }
+
+    pub fn key(&self) -> StateKey {
+        StateKey {
+            player: self.player,
+            pieces: self.pieces.clone(),
+            light_color: self.light_color.clone(),
+            plates: self.plates.clone(),
+            crushed: self.crushed,
+        }
+    }
+
+    pub fn step(&mut self, direction: &Direction) -> Outcome {
+        if self.crushed { return Outcome::default(); }
+        // The room's shared, so keeping a copy to go back to is cheap.
+        let before = self.clone();
+        let mut outcome = self.resolve_step(direction);
+        if self.is_player_trapped() {
+            match self.rules.overlap {
+                Overlap::Forbid => {
+                    *self = before;
+                    return Outcome::default();
+                },
+                Overlap::Eject => match self.nearest_empty_tile() {
+                    Some((x, y)) => {
+                        self.player = (x, y);
+                        outcome.actions.push(GameAction::Eject(x, y));
+                    },
+                    None => {
+                        self.crushed = true;
+                        outcome.actions.push(GameAction::Crush);
+                    },
+                },
+                Overlap::Crush => {
+                    self.crushed = true;
+                    outcome.actions.push(GameAction::Crush);
+                },
+            }
+        }
+        if outcome.is_move() { self.stats.moves += 1; }
+        if outcome.is_rearranged() {
+            self.stats.pushes += 1;
+            self.remove_pieces(&outcome);
+            self.relight();
+            // With no exit to head for, filling the last goal is the win, as in plain Sokoban.
+            let has_exit = self.pieces.iter().any(|p| p.kind == Kind::Exit);
+            if !has_exit && self.room.goals().next().is_some() && self.goals_filled() {
+                outcome.actions.push(GameAction::Win);
+            }
+        }
+        outcome
+    }
+
+    fn resolve_step(&mut self, direction: &Direction) -> Outcome {
+        let mut outcome = Outcome::default();
+        let (x, y) = self.player;
+        let (nx, ny) = direction.from(x, y);
+        if !self.tile_is_passable(nx, ny) { return outcome; }
+        if let Some(piece_id) = self.piece_id_at(nx, ny) {
+            if !self.approach(piece_id, direction, &mut outcome) { return outcome; }
+        }
+        self.player = (nx, ny);
+        outcome.walked = true;
+        outcome
+    }
+
+    /// Deal with the player walking into a piece, returning whether they get to move onto its tile.
+    fn approach(&mut self, piece_id: usize, direction: &Direction, outcome: &mut Outcome) -> bool {
+        let piece = &self.pieces[piece_id];
+        match &piece.kind {
+            // Lit blocks are intangible: walk right through them, onto any switch underneath.
+            Kind::Block(color) if self.tile_in_light(piece.x, piece.y, color) => {
+                match self.flat_piece_id_at(piece.x, piece.y) {
+                    Some(switch_id) => self.approach(switch_id, direction, outcome),
+                    None => true,
+                }
+            },
+            Kind::Block(_) => {
+                let chain = self.chain(piece_id, direction);
+                self.push(&chain, direction, outcome)
+            },
+            Kind::Mirror(_) | Kind::Lamp(_) => self.push(&[piece_id], direction, outcome),
+            Kind::Water => self.tile_in_light(piece.x, piece.y, &Color::Blue),
+            // Exits stay locked, as good as walls, until every goal's been filled.
+            Kind::Exit if !self.goals_filled() => false,
+            Kind::Exit => {
+                outcome.actions.push(GameAction::Win);
+                true
+            },
+            Kind::LightSwitch(color) => {
+                let color = color.clone();
+                self.change_light(color, outcome);
+                true
+            },
+            // Only a block is heavy enough to hold a plate down, or worth painting.
+            Kind::PressurePlate(_) | Kind::Paint(_) => true,
+            Kind::Lightbulb(_) | Kind::Pit => false,
+        }
+    }
+
+    /// The block the player is pushing, followed by every block lined up in front of it that gets
+    /// shoved along too, if the level allows it. Lit blocks are intangible, so they're never part
+    /// of a chain: one in the way stops it like any other piece.
+    fn chain(&self, piece_id: usize, direction: &Direction) -> Vec<usize> {
+        let mut chain = vec![piece_id];
+        if !self.rules.chain_push { return chain; }
+        let (mut x, mut y) = (self.pieces[piece_id].x, self.pieces[piece_id].y);
+        loop {
+            let (nx, ny) = direction.from(x, y);
+            let next_id = match self.piece_id_at(nx, ny) {
+                Some(next_id) => next_id,
+                None => { return chain; },
+            };
+            match &self.pieces[next_id].kind {
+                Kind::Block(color) if !self.tile_in_light(nx, ny, color) => chain.push(next_id),
+                _ => { return chain; },
+            }
+            x = nx;
+            y = ny;
+        }
+    }
+
+    /// Push a line of pieces, nearest first, into whatever's in front of them, and carry out
+    /// whatever `interaction` says happens. Returns whether the pieces moved along.
+    fn push(&mut self, chain: &[usize], direction: &Direction, outcome: &mut Outcome) -> bool {
+        let front_id = chain[chain.len() - 1];
+        let front = &self.pieces[front_id];
+        let (fx, fy) = (front.x, front.y);
+        let (nx, ny) = direction.from(fx, fy);
+        if !self.tile_is_passable(nx, ny) { return false; }
+        let other_id = match self.piece_id_at(nx, ny) {
+            Some(other_id) => other_id,
+            None => {
+                self.shove(chain, direction, outcome);
+                return true;
+            },
+        };
+        let effects = interaction(&self.pieces[front_id].kind, &self.pieces[other_id].kind);
+        let moves = effects.contains(&Effect::Enter);
+        for effect in effects {
+            match effect {
+                Effect::Enter => self.shove(chain, direction, outcome),
+                Effect::RemovePushed => {
+                    // Taken away where it stands, it lets up any plate it was holding down.
+                    if !moves {
+                        if let Some(color) = self.release_plate(fx, fy) { self.change_light(color, outcome); }
+                    }
+                    outcome.actions.push(GameAction::Remove(front_id));
+                },
+                Effect::RemoveOther => outcome.actions.push(GameAction::Remove(other_id)),
+                Effect::Switch => {
+                    if let Kind::LightSwitch(color) = &self.pieces[other_id].kind {
+                        let color = color.clone();
+                        self.change_light(color, outcome);
+                    }
+                },
+                Effect::Press => {
+                    if let Kind::PressurePlate(color) = &self.pieces[other_id].kind {
+                        let color = color.clone();
+                        self.plates.push(((nx, ny), self.light_color.clone()));
+                        self.change_light(color, outcome);
+                    }
+                },
+                Effect::Paint => {
+                    if let Kind::Paint(color) = &self.pieces[other_id].kind {
+                        let color = color.clone();
+                        self.pieces[front_id].kind = Kind::Block(color.clone());
+                        outcome.actions.push(GameAction::Recolor(front_id, color));
+                    }
+                },
+                Effect::Fill => Rc::make_mut(&mut self.room).set_tile(nx, ny, Tile::Sunken),
+            }
+        }
+        moves
+    }
+
+    /// Move a line of pieces along a tile. Only the tile at the back is left empty, so that's the
+    /// only one where a plate might be let up.
+    fn shove(&mut self, chain: &[usize], direction: &Direction, outcome: &mut Outcome) {
+        let back = &self.pieces[chain[0]];
+        let (x, y) = (back.x, back.y);
+        for &piece_id in chain {
+            let piece = &mut self.pieces[piece_id];
+            let (nx, ny) = direction.from(piece.x, piece.y);
+            piece.x = nx;
+            piece.y = ny;
+        }
+        outcome.pushed = chain.to_vec();
+        if let Some(color) = self.release_plate(x, y) { self.change_light(color, outcome); }
+    }
+
+    fn change_light(&mut self, color: Color, outcome: &mut Outcome) {
+        self.light_color = color.clone();
+        outcome.actions.push(GameAction::ColorChange(color));
+    }
+
+    /// Let up the pressure plate at a tile, if it's held down, returning the light to put back.
+    /// If something else has changed the light since the plate went down, it's left alone.
+    fn release_plate(&mut self, x: i32, y: i32) -> Option<Color> {
+        let idx = self.plates.iter().position(|(at, _)| *at == (x, y))?;
+        let (_, restored) = self.plates.remove(idx);
+        match &self.pieces[self.flat_piece_id_at(x, y)?].kind {
+            Kind::PressurePlate(color) if *color == self.light_color => Some(restored),
+            _ => None,
+        }
+    }
+
+    /// Whether the player is standing inside a solid block.
+    fn is_player_trapped(&self) -> bool {
+        let (x, y) = self.player;
+        match self.piece_at(x, y).map(|piece| &piece.kind) {
+            Some(Kind::Block(color)) => !self.tile_in_light(x, y, color),
+            _ => false,
+        }
+    }
+
+    /// The closest tile to the player with nothing on it, if there's anywhere at all.
+    fn nearest_empty_tile(&self) -> Option<(i32, i32)> {
+        let mut seen = HashSet::new();
+        let mut queue = VecDeque::new();
+        seen.insert(self.player);
+        queue.push_back(self.player);
+        while let Some((x, y)) = queue.pop_front() {
+            if (x, y) != self.player && self.piece_id_at(x, y).is_none() { return Some((x, y)); }
+            for direction in Direction::ALL.iter() {
+                let next = direction.from(x, y);
+                if self.tile_is_passable(next.0, next.1) && seen.insert(next) { queue.push_back(next); }
+            }
+        }
+        None
+    }
+
+    /// Take out every piece the step removed, all at once so the indices stay meaningful until then.
+    fn remove_pieces(&mut self, outcome: &Outcome) {
+        let removed: Vec<_> = outcome.removed().collect();
+        let mut idx = 0;
+        self.pieces.retain(|_| { let keep = !removed.contains(&idx); idx += 1; keep });
+    }
+
+    /// Whether every goal has a block on it that suits it. Trivially true in rooms without goals.
+    pub fn goals_filled(&self) -> bool {
+        self.room.goals().all(|(x, y, goal)| self.pieces.iter().any(|p| {
+            p.x == x && p.y == y
+                && matches!(&p.kind, Kind::Block(color) if *goal == Color::Gray || color == goal)
+        }))
+    }
+
+    pub fn tile_is_passable(&self, x: i32, y: i32) -> bool {
+        let tile = self.room.tile_at(x, y);
+        tile.is_some_and(|tile| tile.is_passable())
+    }
+
+    /// Whether light of a color is shining on a tile, counting mixed colors as lit only where
+    /// every one of their primaries is on and reaches it.
+    pub fn tile_in_light(&self, x: i32, y: i32, color: &Color) -> bool {
+        match color {
+            Color::White => true,
+            Color::Gray => false,
+            _ => color.primaries()
+                .all(|primary| self.light_color.contains(&primary) && self.lighting.tile_in_light(x, y, &primary)),
+        }
+    }
+
+    /// The piece on a tile, or whatever's on top if a block is sitting on a switch.
+    pub fn piece_id_at(&self, x: i32, y: i32) -> Option<usize> {
+        self.pieces.iter()
+            .position(|p| p.x == x && p.y == y && !p.kind.is_flat())
+            .or_else(|| self.flat_piece_id_at(x, y))
+    }
+
+    fn flat_piece_id_at(&self, x: i32, y: i32) -> Option<usize> {
+        self.pieces.iter()
+            .position(|p| p.x == x && p.y == y && p.kind.is_flat())
+    }
+
+    pub fn piece_at(&self, x: i32, y: i32) -> Option<&Piece> {
+        let idx = self.piece_id_at(x, y)?;
+        Some(&self.pieces[idx])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn level(text: &str) -> Puzzle {
+        Room::parse(text.as_bytes()).unwrap_or_else(|e| panic!("test level is malformed: {}", e))
+    }
+
+    #[test]
+    fn mirrors_slide_until_a_piece_is_in_the_way() {
+        let mut puzzle = level("W\n#######\n#a/.1z#\n#######\n");
+        assert!(puzzle.step(&Direction::East).is_move());
+        assert_eq!(puzzle.player, (2, 1));
+        assert_eq!(puzzle.piece_at(3, 1).map(|piece| &piece.kind), Some(&Kind::Mirror(Slant::Forward)));
+        // Not even a switch lying flat on the floor lets it by.
+        assert!(!puzzle.step(&Direction::East).is_move());
+        assert_eq!(puzzle.player, (2, 1));
+    }
+
+    #[test]
+    fn chain_pushes_only_with_the_rule() {
+        let mut puzzle = level("W\n#######\n#akk..#\n#######\n");
+        assert!(!puzzle.step(&Direction::East).is_move());
+        assert_eq!(puzzle.player, (1, 1));
+
+        let mut puzzle = level("W chain\n#######\n#akk..#\n#######\n");
+        let outcome = puzzle.step(&Direction::East);
+        assert_eq!(outcome.pushed, vec![0, 1]);
+        assert_eq!(puzzle.player, (2, 1));
+        assert_eq!((puzzle.pieces[0].x, puzzle.pieces[1].x), (3, 4));
+    }
+
+    #[test]
+    fn blocks_and_water_take_each_other_out() {
+        let mut puzzle = level("W\n######\n#ak~z#\n######\n");
+        let outcome = puzzle.step(&Direction::East);
+        // The block goes first, then the water.
+        assert_eq!(outcome.removed().collect::<Vec<_>>(), vec![0, 1]);
+        assert_eq!(puzzle.player, (1, 1));
+        assert!(puzzle.pieces.iter().all(|p| p.kind == Kind::Exit));
+    }
+
+    #[test]
+    fn filled_water_turns_into_floor_to_walk_over() {
+        let mut puzzle = level("W\n######\n#ak~z#\n######\n");
+        // Water stops the player, unless it's lit blue.
+        assert!(!level("W\n#####\n#a~z#\n#####\n").step(&Direction::East).is_move());
+        puzzle.step(&Direction::East);
+        assert!(matches!(puzzle.room.tile_at(3, 1), Some(Tile::Sunken)));
+        puzzle.step(&Direction::East);
+        puzzle.step(&Direction::East);
+        assert!(puzzle.step(&Direction::East).is_win());
+    }
+
+    #[test]
+    fn pits_swallow_blocks_and_stay_open() {
+        let mut puzzle = level("W\n#######\n#ako..#\n#######\n");
+        let outcome = puzzle.step(&Direction::East);
+        assert_eq!(outcome.removed().count(), 1);
+        assert_eq!(puzzle.player, (2, 1));
+        assert_eq!(puzzle.pieces.iter().map(|p| &p.kind).collect::<Vec<_>>(), vec![&Kind::Pit]);
+        assert!(!puzzle.step(&Direction::East).is_move());
+    }
+
+    #[test]
+    fn paint_colors_blocks_pushed_over_it() {
+        let mut puzzle = level("W\n#######\n#akd..#\n#######\n");
+        puzzle.step(&Direction::East);
+        assert_eq!(puzzle.piece_at(3, 1).map(|piece| &piece.kind), Some(&Kind::Block(Color::Red)));
+    }
+
+    #[test]
+    fn blocks_work_switches() {
+        let mut puzzle = level("W\n#######\n#ak2..#\n#######\n");
+        puzzle.step(&Direction::East);
+        assert_eq!(puzzle.light_color, Color::Green);
+        // A plain switch stays switched once the block moves on.
+        puzzle.step(&Direction::East);
+        assert_eq!(puzzle.light_color, Color::Green);
+    }
+
+    #[test]
+    fn pressure_plates_put_the_light_back_when_let_up() {
+        let mut puzzle = level("W\n#######\n#akE..#\n#######\n");
+        puzzle.step(&Direction::East);
+        assert_eq!(puzzle.light_color, Color::Green);
+        puzzle.step(&Direction::East);
+        assert_eq!(puzzle.light_color, Color::Gray);
+        assert!(puzzle.plates.is_empty());
+    }
+
+    #[test]
+    fn only_gray_blocks_cast_shadows() {
+        // The red bulb on the left shines down the corridor, past whatever's in the middle.
+        let lit_past = |piece: char| {
+            let puzzle = level(&format!("R\n########\n#R.{}..a#\n#z######\n", piece));
+            puzzle.tile_in_light(5, 1, &Color::Red)
+        };
+        assert!(!lit_past('k'));
+        assert!(lit_past('r'));
+        assert!(lit_past('b'));
+        assert!(lit_past('9'));
+    }
+
+    #[test]
+    fn mirrors_turn_light_instead_of_stopping_it() {
+        // Nothing in a straight line from the bulb reaches the bottom of the shaft, but the
+        // mirror bends the light down into it.
+        let puzzle = level("R\n#######\n#R.\\#z#\n###.###\n###a###\n#######\n");
+        assert!(puzzle.tile_in_light(3, 3, &Color::Red));
+        let puzzle = level("R\n#######\n#R.k#z#\n###.###\n###a###\n#######\n");
+        assert!(!puzzle.tile_in_light(3, 3, &Color::Red));
+    }
+
+    #[test]
+    fn exits_stay_locked_until_the_goals_are_filled() {
+        let mut puzzle = level("W\n#######\n#.k:..#\n#a...z#\n#######\n");
+        use Direction::*;
+        for direction in &[East, East, East] { puzzle.step(direction); }
+        assert!(!puzzle.step(&East).is_move());
+        for direction in &[West, West, West, North, East, South, East, East] { puzzle.step(direction); }
+        assert!(puzzle.goals_filled());
+        assert!(puzzle.step(&East).is_win());
+    }
+
+    /// Walk through a lit red block onto a green switch, which turns the block solid around the
+    /// player, and return what happened on that last move.
+    fn trap(rule: &str) -> (Puzzle, Outcome) {
+        let mut puzzle = level(&format!("G {}\n##########\n#....R...#\n#ar2.....#\n#....1..z#\n##########\n", rule));
+        use Direction::*;
+        for direction in &[East, South, East, East, East, North, West] {
+            assert!(puzzle.step(direction).is_move());
+        }
+        assert_eq!((puzzle.player, &puzzle.light_color), ((4, 2), &Color::Red));
+        let outcome = puzzle.step(&West);
+        (puzzle, outcome)
+    }
+
+    #[test]
+    fn overlap_forbid_keeps_the_player_out() {
+        let (puzzle, outcome) = trap("forbid");
+        assert!(!outcome.is_move());
+        assert_eq!((puzzle.player, &puzzle.light_color), ((4, 2), &Color::Red));
+    }
+
+    #[test]
+    fn overlap_forbid_undoes_everything_the_push_did() {
+        // Standing in a lit red block, push the block holding down the red plate into the water.
+        // The plate lets up, the light goes back to green, and the red block turns solid.
+        let mut puzzle = level("G forbid\n########\n#R.a...#\n#..k...#\n#.rD~..#\n########\n");
+        use Direction::*;
+        for direction in &[South, West, South] {
+            assert!(puzzle.step(direction).is_move());
+        }
+        assert_eq!((puzzle.player, &puzzle.light_color), ((2, 3), &Color::Red));
+        let pieces = puzzle.pieces.clone();
+        assert!(!puzzle.step(&East).is_move());
+        assert_eq!((puzzle.player, &puzzle.light_color), ((2, 3), &Color::Red));
+        assert_eq!(puzzle.pieces, pieces);
+        assert_eq!(puzzle.plates.len(), 1);
+        assert!(matches!(puzzle.room.tile_at(4, 3), Some(Tile::Floor)));
+    }
+
+    #[test]
+    fn overlap_crush_ends_the_run() {
+        let (mut puzzle, outcome) = trap("crush");
+        assert!(outcome.actions.iter().any(|action| matches!(action, GameAction::Crush)));
+        assert!(puzzle.crushed);
+        assert_eq!(puzzle.light_color, Color::Green);
+        assert!(!puzzle.step(&Direction::East).is_move());
+    }
+
+    #[test]
+    fn overlap_eject_throws_the_player_clear() {
+        let (puzzle, outcome) = trap("eject");
+        let (x, y) = puzzle.player;
+        assert!(outcome.actions.iter().any(|action| matches!(action, GameAction::Eject(ex, ey) if (*ex, *ey) == (x, y))));
+        assert_eq!((x - 3).abs() + (y - 2).abs(), 1);
+        assert!(puzzle.piece_at(x, y).is_none());
+        assert!(!puzzle.crushed);
+    }
+
+    #[test]
+    fn filling_the_last_goal_wins_without_an_exit() {
+        let mut puzzle = level("W\n#####\n#ak:#\n#####\n");
+        assert!(puzzle.step(&Direction::East).is_win());
+    }
+
+    #[test]
+    fn room_without_exit_or_goals_is_never_won() {
+        // Like a level still being built in the editor.
+        let mut puzzle = level("W\n#####\n#ak.#\n#####\n");
+        let outcome = puzzle.step(&Direction::East);
+        assert_eq!(outcome.pushed, vec![0]);
+        assert!(!outcome.is_win());
+    }
+}
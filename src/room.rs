@@ -1,197 +1,378 @@
-use piston_window::{Context, DrawState, Image};
-use opengl_graphics::GlGraphics;
-use opengl_graphics::Texture as GlTexture;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
 use geo::polygon;
 use crate::color::Color;
-use crate::entity::{Block, Entity, Exit, Lightbulb, LightSwitch, Player, Water};
-use crate::line_of_sight::{line_of_sight, Visibility};
-
-const ONE_START_MSG: &str = "level must have exactly one starting position";
-pub const NUM_LEVELS: usize = 8;
-const LEVELS: [&[u8]; NUM_LEVELS] = [
-    include_bytes!("../bin/levels/level01.skb"),
-    include_bytes!("../bin/levels/level02.skb"),
-    include_bytes!("../bin/levels/level03.skb"),
-    include_bytes!("../bin/levels/level04.skb"),
-    include_bytes!("../bin/levels/level05.skb"),
-    include_bytes!("../bin/levels/level06.skb"),
-    include_bytes!("../bin/levels/level07.skb"),
-    include_bytes!("../bin/levels/level08.skb"),
-];
+use crate::lighting::Lighting;
+use crate::puzzle::{Kind, Overlap, Piece, Puzzle, Rules, Slant};
+
 const TITLE_LEVEL: &[u8] = include_bytes!("../bin/levels/title.skb");
 const TILE_SIZE: f64 = 16.;
-const WALL: [f64; 4] = [32., 0., TILE_SIZE, TILE_SIZE];
-const FLOOR: [f64; 4] = [32., 16., TILE_SIZE, TILE_SIZE];
+// How many arrangements of light and shadow to remember before starting over.
+// The solver can visit far more than this, and each one holds a fan per bulb.
+const LIGHTING_CACHE_SIZE: usize = 4096;
+
+/// Why a level file couldn't be loaded. Lines and columns count from 1, header included.
+#[derive(Debug, PartialEq)]
+pub enum LevelError {
+    MissingHeader,
+    UnknownHeader { column: usize, chr: char },
+    UnknownRule { column: usize, rule: String },
+    Empty,
+    RaggedRow { line: usize, expected: usize, found: usize },
+    UnknownGlyph { line: usize, column: usize, chr: char },
+    DuplicateStart { line: usize, column: usize },
+    MissingStart,
+    MissingExit,
+}
+
+impl fmt::Display for LevelError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LevelError::MissingHeader => write!(f, "line 1: missing starting light color"),
+            LevelError::UnknownHeader { column, chr } => {
+                write!(f, "line 1, column {}: unknown starting light color {:?} (expected one of R, G, B, Y, C, M, W)", column, chr)
+            },
+            LevelError::UnknownRule { column, rule } => {
+                write!(f, "line 1, column {}: unknown rule {:?} (expected chain, crush, eject, or forbid)", column, rule)
+            },
+            LevelError::Empty => write!(f, "line 2: level has no rows"),
+            LevelError::RaggedRow { line, expected, found } => {
+                write!(f, "line {}: row is {} tiles wide, but the first row is {}", line, found, expected)
+            },
+            LevelError::UnknownGlyph { line, column, chr } => {
+                write!(f, "line {}, column {}: unknown tile {:?}", line, column, chr)
+            },
+            LevelError::DuplicateStart { line, column } => {
+                write!(f, "line {}, column {}: level must have exactly one starting position, found a second", line, column)
+            },
+            LevelError::MissingStart => write!(f, "level must have exactly one starting position, found none"),
+            LevelError::MissingExit => write!(f, "level has no exit, and no goals to fill"),
+        }
+    }
+}
+
+impl std::error::Error for LevelError {}
 
 #[derive(Clone)]
 pub enum Tile {
     Floor,
     Wall,
+    /// Stops the player and blocks, but not the light.
+    Glass,
+    /// Stops the light, but not the player or blocks.
+    Curtain,
+    /// Tinted glass: only lets through the light channels of its color.
+    Filter(Color),
+    /// Water with a block sunk into it, which makes it floor again.
+    Sunken,
+    /// Floor that needs a block on it before the exits will open: any block if it's gray,
+    /// otherwise one of its color.
+    Goal(Color),
 }
 use Tile::*;
 
 impl Tile {
-    fn from_chr(chr: char) -> Self {
-        match chr {
+    /// The tile a level file glyph stands for, if it's one of the tiles. Pieces stand on floor.
+    pub fn from_chr(chr: char) -> Option<Self> {
+        let tile = match chr {
+            '.' => Floor,
             '#' => Wall,
-            _ => Floor,
-        }
-    }
-
-    pub fn sprite(&self, x: usize, y: usize) -> Image {
-        let src = match self {
-            Wall => WALL,
-            Floor => FLOOR,
+            '=' => Glass,
+            '%' => Curtain,
+            '!' => Filter(Color::Red),
+            '@' => Filter(Color::Green),
+            '$' => Filter(Color::Blue),
+            '^' => Filter(Color::Yellow),
+            '&' => Filter(Color::Cyan),
+            '*' => Filter(Color::Magenta),
+            '_' => Sunken,
+            ':' => Goal(Color::Gray),
+            'T' => Goal(Color::Red),
+            'U' => Goal(Color::Green),
+            'V' => Goal(Color::Blue),
+            'X' => Goal(Color::Yellow),
+            'Y' => Goal(Color::Cyan),
+            'Z' => Goal(Color::Magenta),
+            _ => { return None; },
         };
-        Image::new()
-            .src_rect(src)
-            .rect([x as f64 * TILE_SIZE, y as f64 * TILE_SIZE, TILE_SIZE, TILE_SIZE])
+        Some(tile)
     }
 
     pub fn is_passable(&self) -> bool {
         match self {
-            Wall => false,
-            Floor => true,
+            Wall | Glass | Filter(_) => false,
+            Floor | Curtain | Sunken | Goal(_) => true,
         }
     }
 
-    pub fn is_transparent(&self) -> bool {
-        // TODO: ᖍ(∙⟞∙)ᖌ
-        self.is_passable()
+    /// Whether light of a primary color passes through the tile.
+    pub fn is_transparent(&self, primary: &Color) -> bool {
+        match self {
+            Wall | Curtain => false,
+            Floor | Glass | Sunken | Goal(_) => true,
+            Filter(color) => color.contains(primary),
+        }
     }
 }
 
-fn to_walls_polygon(tiles: &[Tile], width: usize) -> geo::MultiPolygon<f64> {
-    let mut polygons = Vec::new();
-    for (i, tile) in tiles.iter().enumerate() {
-        if !tile.is_transparent() {
-            let x = (i % width) as f64 * TILE_SIZE;
-            let y = (i / width) as f64 * TILE_SIZE;
-
-            polygons.push(polygon![
-                exterior: [
-                    (x: x, y: y),
-                    (x: x + TILE_SIZE, y: y),
-                    (x: x + TILE_SIZE, y: y + TILE_SIZE),
-                    (x: x, y: y + TILE_SIZE),
-                ],
-                interiors: [],
-            ]);
+/// The piece a level file glyph stands for, if it's one of the pieces.
+pub fn kind_from_chr(chr: char) -> Option<Kind> {
+    let kind = match chr {
+        'k' => Kind::Block(Color::Gray),
+        'r' => Kind::Block(Color::Red),
+        'g' => Kind::Block(Color::Green),
+        'b' => Kind::Block(Color::Blue),
+        'y' => Kind::Block(Color::Yellow),
+        'c' => Kind::Block(Color::Cyan),
+        'm' => Kind::Block(Color::Magenta),
+        'w' => Kind::Block(Color::White),
+        'R' => Kind::Lightbulb(Color::Red),
+        'G' => Kind::Lightbulb(Color::Green),
+        'B' => Kind::Lightbulb(Color::Blue),
+        '7' => Kind::Lamp(Color::Red),
+        '8' => Kind::Lamp(Color::Green),
+        '9' => Kind::Lamp(Color::Blue),
+        '1' => Kind::LightSwitch(Color::Red),
+        '2' => Kind::LightSwitch(Color::Green),
+        '3' => Kind::LightSwitch(Color::Blue),
+        '4' => Kind::LightSwitch(Color::Yellow),
+        '5' => Kind::LightSwitch(Color::Cyan),
+        '6' => Kind::LightSwitch(Color::Magenta),
+        '0' => Kind::LightSwitch(Color::Gray),
+        'D' => Kind::PressurePlate(Color::Red),
+        'E' => Kind::PressurePlate(Color::Green),
+        'F' => Kind::PressurePlate(Color::Blue),
+        'H' => Kind::PressurePlate(Color::Yellow),
+        'I' => Kind::PressurePlate(Color::Cyan),
+        'J' => Kind::PressurePlate(Color::Magenta),
+        'O' => Kind::PressurePlate(Color::Gray),
+        'd' => Kind::Paint(Color::Red),
+        'e' => Kind::Paint(Color::Green),
+        'f' => Kind::Paint(Color::Blue),
+        'h' => Kind::Paint(Color::Yellow),
+        'i' => Kind::Paint(Color::Cyan),
+        'j' => Kind::Paint(Color::Magenta),
+        'x' => Kind::Paint(Color::Gray),
+        'o' => Kind::Pit,
+        '/' => Kind::Mirror(Slant::Forward),
+        '\\' => Kind::Mirror(Slant::Back),
+        'z' => Kind::Exit,
+        '~' => Kind::Water,
+        _ => { return None; },
+    };
+    Some(kind)
+}
+
+/// The glyph for what starts out on a gray goal, for the glyphs that stand for both at once:
+/// the player (`+`) or a gray block (`;`).
+fn on_goal(chr: char) -> Option<char> {
+    match chr {
+        '+' => Some('a'),
+        ';' => Some('k'),
+        _ => None,
+    }
+}
+
+/// The rules a level turns on, as words following its starting light color.
+fn parse_rules(words: &[u8]) -> Result<Rules, LevelError> {
+    let mut rules = Rules::default();
+    let mut column = 2;
+    for word in words.split(|&c| c == b' ') {
+        match word {
+            b"" => (),
+            b"chain" => { rules.chain_push = true; },
+            b"crush" => { rules.overlap = Overlap::Crush; },
+            b"eject" => { rules.overlap = Overlap::Eject; },
+            b"forbid" => { rules.overlap = Overlap::Forbid; },
+            _ => {
+                let rule = String::from_utf8_lossy(word).into_owned();
+                return Err(LevelError::UnknownRule { column, rule });
+            },
         }
+        column += word.len() + 1;
     }
-    geo::MultiPolygon(polygons)
+    Ok(rules)
 }
 
-type Game = (Room, Player, Vec<Entity>, Color);
+fn tile_polygon(x: usize, y: usize) -> geo::Polygon<f64> {
+    let x = x as f64 * TILE_SIZE;
+    let y = y as f64 * TILE_SIZE;
+    polygon![
+        exterior: [
+            (x: x, y: y),
+            (x: x + TILE_SIZE, y: y),
+            (x: x + TILE_SIZE, y: y + TILE_SIZE),
+            (x: x, y: y + TILE_SIZE),
+        ],
+        interiors: [],
+    ]
+}
+
+fn to_walls_polygon(tiles: &[Tile], width: usize, primary: &Color) -> geo::MultiPolygon<f64> {
+    let polygons = tiles.iter().enumerate()
+        .filter(|(_, tile)| !tile.is_transparent(primary))
+        .map(|(i, _)| tile_polygon(i % width, i / width))
+        .collect();
+    geo::MultiPolygon(polygons)
+}
 
+#[derive(Clone)]
 pub struct Room {
     width: usize,
     height: usize,
     tiles: Vec<Tile>,
-    sees_color: Vec<[bool; 3]>
+    // What stops each channel of light, in channel order.
+    walls_polygons: Vec<geo::MultiPolygon<f64>>,
+    // Every copy of a puzzle shares its room, so they can share the work of lighting it too.
+    // A room copied to have its tiles changed keeps sharing it, unless the light changes too.
+    lighting_cache: Rc<RefCell<HashMap<Vec<Piece>, Rc<Lighting>>>>,
 }
 
 impl Room {
-    pub fn new(level: usize) -> Game {
-        Room::from_file(LEVELS[level])
+    pub fn new_title() -> Puzzle {
+        // The title screen is all set dressing, so it's the one room that needn't have an exit.
+        Room::parse(TITLE_LEVEL)
+            .unwrap_or_else(|e| panic!("title level is malformed: {}", e))
     }
 
-    pub fn new_title() -> Game {
-        Room::from_file(TITLE_LEVEL)
+    pub fn from_file(bytes: &[u8]) -> Result<Puzzle, LevelError> {
+        let puzzle = Room::parse(bytes)?;
+        if !puzzle.pieces.iter().any(|p| p.kind == Kind::Exit) && puzzle.room.goals().next().is_none() {
+            return Err(LevelError::MissingExit);
+        }
+        Ok(puzzle)
     }
 
-    pub fn from_file(bytes: &[u8]) -> Game {
-        let starting_color = match bytes[0] {
-            b'R' => Color::Red,
-            b'G' => Color::Green,
-            b'B' => Color::Blue,
-            _ => Color::Gray,
+    /// Reads a level without insisting on an exit, for rooms that aren't meant to be beaten
+    /// (the title screen, or a level still being built in the editor).
+    pub fn parse(bytes: &[u8]) -> Result<Puzzle, LevelError> {
+        let mut lines = bytes.split(|&c| c == b'\n')
+            .map(|line| line.strip_suffix(b"\r").unwrap_or(line));
+        let header = lines.next().unwrap_or_default();
+        let starting_color = match header.first() {
+            Some(b'R') => Color::Red,
+            Some(b'G') => Color::Green,
+            Some(b'B') => Color::Blue,
+            Some(b'Y') => Color::Yellow,
+            Some(b'C') => Color::Cyan,
+            Some(b'M') => Color::Magenta,
+            Some(b'W') => Color::Gray,
+            None => { return Err(LevelError::MissingHeader); },
+            Some(&chr) => { return Err(LevelError::UnknownHeader { column: 1, chr: chr as char }); },
         };
-        let first_line = bytes.iter().position(|&c| c == b'\n').unwrap() + 1;
-        let bytes = &bytes[first_line..];
-        let width = bytes.iter().position(|&c| c == b'\n').unwrap();
-        let tiles: Vec<_> = bytes.iter()
-            .filter_map(|&c| {
-                (c != b'\n').then(|| Tile::from_chr(c as char))
-            }).collect();
-        let height = tiles.len() / width;
-
-        let walls_polygon = to_walls_polygon(&tiles, width);
-        let mut sees_color = vec![[false, false, false]; tiles.len()];
-
-        let mut x = 0;
-        let mut y = 0;
+        let rules = parse_rules(&header[1..])?;
+        let mut rows: Vec<_> = lines.collect();
+        if rows.last().is_some_and(|row| row.is_empty()) { rows.pop(); }
+        let width = rows.first().map_or(0, |row| row.len());
+        if width == 0 { return Err(LevelError::Empty); }
+        for (y, row) in rows.iter().enumerate() {
+            if row.len() != width {
+                return Err(LevelError::RaggedRow { line: y + 2, expected: width, found: row.len() });
+            }
+        }
+        let height = rows.len();
+        let tiles: Vec<_> = rows.iter()
+            .flat_map(|row| row.iter().map(|&c| match on_goal(c as char) {
+                Some(_) => Goal(Color::Gray),
+                None => Tile::from_chr(c as char).unwrap_or(Floor),
+            }))
+            .collect();
+
+        let walls_polygons = Color::White.primaries()
+            .map(|primary| to_walls_polygon(&tiles, width, &primary))
+            .collect();
+        let room = Room { width, height, tiles, walls_polygons, lighting_cache: Rc::default() };
+
         let mut player = None;
-        let mut entities = Vec::new();
-        for &byte in bytes {
-            match byte as char {
-                'a' => {
-                    if player.is_some() { panic!("{}", ONE_START_MSG); }
-                    player = Some(Player::new(x, y));
-                },
-                'k' => { entities.push(Entity::Block(Block::new(x, y, Color::Gray))); },
-                'r' => { entities.push(Entity::Block(Block::new(x, y, Color::Red))); },
-                'g' => { entities.push(Entity::Block(Block::new(x, y, Color::Green))); },
-                'b' => { entities.push(Entity::Block(Block::new(x, y, Color::Blue))); },
-                'y' => { entities.push(Entity::Block(Block::new(x, y, Color::Yellow))); },
-                'c' => { entities.push(Entity::Block(Block::new(x, y, Color::Cyan))); },
-                'm' => { entities.push(Entity::Block(Block::new(x, y, Color::Magenta))); },
-                'w' => { entities.push(Entity::Block(Block::new(x, y, Color::White))); },
-                'R' => {
-                    let Visibility { polygon_pts, tiles } = line_of_sight(x, y, width, height, &walls_polygon);
-                    for idx in tiles {
-                        sees_color[idx][0] = true;
-                    }
-                    entities.push(Entity::Lightbulb(Lightbulb::new(x, y, Color::Red, polygon_pts)));
-                },
-                'G' => {
-                    let Visibility { polygon_pts, tiles } = line_of_sight(x, y, width, height, &walls_polygon);
-                    for idx in tiles {
-                        sees_color[idx][1] = true;
-                    }
-                    entities.push(Entity::Lightbulb(Lightbulb::new(x, y, Color::Green, polygon_pts)));
-                },
-                'B' => {
-                    let Visibility { polygon_pts, tiles } = line_of_sight(x, y, width, height, &walls_polygon);
-                    for idx in tiles {
-                        sees_color[idx][2] = true;
-                    }
-                    entities.push(Entity::Lightbulb(Lightbulb::new(x, y, Color::Blue, polygon_pts)));
-                },
-                '1' => { entities.push(Entity::LightSwitch(LightSwitch::new(x, y, Color::Red))); },
-                '2' => { entities.push(Entity::LightSwitch(LightSwitch::new(x, y, Color::Green))); },
-                '3' => { entities.push(Entity::LightSwitch(LightSwitch::new(x, y, Color::Blue))); },
-                'z' => { entities.push(Entity::Exit(Exit::new(x, y))); },
-                '~' => { entities.push(Entity::Water(Water::new(x, y))); },
-                '\n' => {
-                    x = 0;
-                    y += 1;
-                    continue;
-                },
-                _ => (),
+        let mut pieces = Vec::new();
+        for (y, row) in rows.iter().enumerate() {
+            for (x, &byte) in row.iter().enumerate() {
+                let (line, column) = (y + 2, x + 1);
+                let (x, y) = (x as i32, y as i32);
+                let chr = on_goal(byte as char).unwrap_or(byte as char);
+                let kind = match chr {
+                    'a' => {
+                        if player.is_some() { return Err(LevelError::DuplicateStart { line, column }); }
+                        player = Some((x, y));
+                        None
+                    },
+                    chr if Tile::from_chr(chr).is_some() => None,
+                    chr => match kind_from_chr(chr) {
+                        Some(kind) => Some(kind),
+                        None => { return Err(LevelError::UnknownGlyph { line, column, chr }); },
+                    },
+                };
+                if let Some(kind) = kind {
+                    pieces.push(Piece { x, y, kind });
+                }
             }
-            x += 1;
         }
 
-        (
-            Room { width, height, tiles, sees_color },
-            player.expect(ONE_START_MSG),
-            entities,
-            starting_color,
-        )
-    }
-
-    pub fn render(&self,
-                  texture: &GlTexture,
-                  draw_state: &DrawState,
-                  context: &Context,
-                  gl: &mut GlGraphics) {
-        for (i, elem) in self.tiles.iter().enumerate() {
-            let x = i % self.width;
-            let y = i / self.width;
-            elem.sprite(x, y)
-                .draw(texture, draw_state, context.transform, gl);
+        let player = player.ok_or(LevelError::MissingStart)?;
+        // Switches and paint go first, so blocks pushed onto them are drawn on top.
+        pieces.sort_by_key(|piece| !piece.kind.is_flat());
+        Ok(Puzzle::new(room, player, pieces, starting_color, rules))
+    }
+
+    /// How the room is lit with the given pieces in it.
+    pub fn lighting(&self, pieces: &[Piece]) -> Rc<Lighting> {
+        // Only the pieces that cast or block light make any difference.
+        let key: Vec<_> = pieces.iter()
+            .filter(|p| p.kind.affects_light())
+            .cloned()
+            .collect();
+        let mut cache = self.lighting_cache.borrow_mut();
+        if let Some(lighting) = cache.get(&key) {
+            return lighting.clone();
         }
+        if cache.len() >= LIGHTING_CACHE_SIZE { cache.clear(); }
+        let lighting = Rc::new(Lighting::new(self, &key));
+        cache.insert(key, lighting.clone());
+        lighting
+    }
+
+    /// Everything light of a primary color can't pass through: the walls and filters that stop it,
+    /// plus any opaque pieces.
+    pub fn occluders(&self, pieces: &[Piece], primary: &Color) -> geo::MultiPolygon<f64> {
+        let cidx = primary.channel().expect("light only comes in primary colors");
+        let mut polygons = self.walls_polygons[cidx].0.clone();
+        polygons.extend(pieces.iter()
+            .filter(|p| p.kind.is_opaque())
+            .map(|p| tile_polygon(p.x as usize, p.y as usize)));
+        geo::MultiPolygon(polygons)
+    }
+
+    /// The reflective faces of any mirrors among the pieces, corner to corner across their tiles.
+    pub fn mirrors(&self, pieces: &[Piece]) -> Vec<geo::Line<f64>> {
+        pieces.iter()
+            .filter_map(|piece| {
+                let slant = match &piece.kind {
+                    Kind::Mirror(slant) => slant,
+                    _ => { return None; },
+                };
+                let left = piece.x as f64 * TILE_SIZE;
+                let top = piece.y as f64 * TILE_SIZE;
+                let (right, bottom) = (left + TILE_SIZE, top + TILE_SIZE);
+                Some(match slant {
+                    Slant::Forward => geo::Line::new((left, bottom), (right, top)),
+                    Slant::Back => geo::Line::new((left, top), (right, bottom)),
+                })
+            })
+            .collect()
+    }
+
+    pub fn tiles(&self) -> &[Tile] {
+        &self.tiles
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
     }
 
     pub fn tile_at(&self, x: i32, y: i32) -> Option<Tile> {
@@ -200,16 +381,32 @@ impl Room {
         self.tiles.get(idx).cloned()
     }
 
-    pub fn tile_in_light(&self, x: i32, y: i32, color: &Color) -> bool {
-        let cidx = match color {
-            Color::Red => 0,
-            Color::Green => 1,
-            Color::Blue => 2,
-            _ => { return false; },
-        };
-        if x < 0 || y < 0 { return false; }
+    /// Where every goal is, and the color of block it wants.
+    pub fn goals(&self) -> impl Iterator<Item = (i32, i32, &Color)> + '_ {
+        let width = self.width;
+        self.tiles.iter().enumerate().filter_map(move |(i, tile)| match tile {
+            Goal(color) => Some(((i % width) as i32, (i / width) as i32, color)),
+            _ => None,
+        })
+    }
+
+    /// Swap in a different tile, as when water's filled in during play.
+    pub fn set_tile(&mut self, x: i32, y: i32, tile: Tile) {
+        if x < 0 || y < 0 || x as usize >= self.width { return; }
         let idx = self.width * (y as usize) + x as usize;
-        self.sees_color.get(idx).map_or(false, |&arr| arr[cidx])
+        let old = match self.tiles.get_mut(idx) {
+            Some(old) => old,
+            None => { return; },
+        };
+        let relit = Color::White.primaries()
+            .any(|primary| old.is_transparent(&primary) != tile.is_transparent(&primary));
+        *old = tile;
+        if relit {
+            self.walls_polygons = Color::White.primaries()
+                .map(|primary| to_walls_polygon(&self.tiles, self.width, &primary))
+                .collect();
+            self.lighting_cache = Rc::default();
+        }
     }
 
     pub fn pixel_width(&self) -> i64 {
@@ -220,3 +417,56 @@ impl Room {
         self.height as i64 * 16
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(text: &str) -> Option<LevelError> {
+        Room::from_file(text.as_bytes()).err()
+    }
+
+    #[test]
+    fn reads_the_header() {
+        assert_eq!(error(""), Some(LevelError::MissingHeader));
+        assert_eq!(error("Q\n#az#\n"), Some(LevelError::UnknownHeader { column: 1, chr: 'Q' }));
+        let puzzle = Room::from_file(b"B\n#az#\n").unwrap();
+        assert_eq!(puzzle.light_color, Color::Blue);
+    }
+
+    #[test]
+    fn reads_rules_after_the_color() {
+        assert_eq!(
+            error("W chain bogus\n#az#\n"),
+            Some(LevelError::UnknownRule { column: 9, rule: "bogus".to_string() }),
+        );
+        let puzzle = Room::from_file(b"B chain eject\n#az#\n").unwrap();
+        assert_eq!(puzzle.light_color, Color::Blue);
+        assert_eq!(puzzle.rules, Rules { chain_push: true, overlap: Overlap::Eject });
+    }
+
+    #[test]
+    fn reports_where_the_rows_go_wrong() {
+        assert_eq!(error("W\n"), Some(LevelError::Empty));
+        assert_eq!(error("W\n####\n#az\n"), Some(LevelError::RaggedRow { line: 3, expected: 4, found: 3 }));
+        assert_eq!(error("W\n#az?#\n"), Some(LevelError::UnknownGlyph { line: 2, column: 4, chr: '?' }));
+    }
+
+    #[test]
+    fn needs_one_start() {
+        assert_eq!(error("W\n#..z#\n"), Some(LevelError::MissingStart));
+        assert_eq!(error("W\n#az#\n#a.#\n"), Some(LevelError::DuplicateStart { line: 3, column: 2 }));
+        // The player standing on a goal counts too.
+        assert_eq!(error("W\n#a+k#\n"), Some(LevelError::DuplicateStart { line: 2, column: 3 }));
+    }
+
+    #[test]
+    fn needs_an_exit_or_goals() {
+        assert_eq!(error("W\n#ak.#\n"), Some(LevelError::MissingExit));
+        assert!(Room::from_file(b"W\r\n#akz#\r\n").is_ok());
+        assert!(Room::from_file(b"W\n#ak:#\n").is_ok());
+        assert!(Room::from_file(b"W\n#+;.#\n").is_ok());
+        // Only `from_file` insists on it.
+        assert!(Room::parse(b"W\n#ak.#\n").is_ok());
+    }
+}
@@ -0,0 +1,192 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use crate::pack::Pack;
+use crate::puzzle::Stats;
+
+// Progress lives in a small text file in the user's data directory, one record per line,
+// with fields separated by tabs:
+//
+//     version  1
+//     last     <pack title>  <level file>
+//     best     <pack title>  <level file>  <moves>  <pushes>
+//
+// A level counts as completed once it has a best. Records this version doesn't recognize, and
+// records with more fields than it knows about, are written back out untouched, as is a newer
+// version number, so an older build won't throw away whatever a newer one saved.
+
+const VERSION: u32 = 1;
+const SAVE_DIR: &str = "colorways";
+const SAVE_FILE: &str = "save.txt";
+
+/// A record's value, along with the line it was read from if that had fields past the ones this
+/// version knows about. Those lines are written back as they were, until the value changes.
+struct Record<T> {
+    value: T,
+    line: Option<String>,
+}
+
+impl<T> Record<T> {
+    fn read(value: T, line: &str, extra: &[&str]) -> Self {
+        Record { value, line: if extra.is_empty() { None } else { Some(line.to_string()) } }
+    }
+}
+
+pub struct Save {
+    path: Option<PathBuf>,
+    version: u32,
+    last: HashMap<String, Record<String>>,
+    best: HashMap<(String, String), Record<Stats>>,
+    unknown: Vec<String>,
+}
+
+impl Save {
+    /// Read the save file, or start fresh if there isn't one (or it's unreadable).
+    pub fn load() -> Self {
+        let path = dirs::data_dir().map(|dir| dir.join(SAVE_DIR).join(SAVE_FILE));
+        let text = match path.as_ref().map(fs::read_to_string) {
+            Some(Ok(text)) => text,
+            Some(Err(e)) if e.kind() != io::ErrorKind::NotFound => {
+                eprintln!("Couldn't read save file, starting fresh: {}", e);
+                String::new()
+            },
+            _ => String::new(),
+        };
+        Save { path, ..Save::parse(&text) }
+    }
+
+    fn parse(text: &str) -> Self {
+        let mut save = Save { path: None, version: VERSION, last: HashMap::new(), best: HashMap::new(), unknown: Vec::new() };
+        for line in text.lines() {
+            let fields: Vec<_> = line.split('\t').collect();
+            match fields.as_slice() {
+                ["version", version] => {
+                    save.version = version.parse().map_or(save.version, |version: u32| version.max(save.version));
+                },
+                ["last", pack, file, extra @ ..] => {
+                    save.last.insert(pack.to_string(), Record::read(file.to_string(), line, extra));
+                },
+                ["best", pack, file, moves, pushes, extra @ ..] => {
+                    match (moves.parse(), pushes.parse()) {
+                        (Ok(moves), Ok(pushes)) => {
+                            let stats = Record::read(Stats { moves, pushes }, line, extra);
+                            save.best.insert((pack.to_string(), file.to_string()), stats);
+                        },
+                        _ => { save.unknown.push(line.to_string()); },
+                    }
+                },
+                [""] => (),
+                _ => { save.unknown.push(line.to_string()); },
+            }
+        }
+        save
+    }
+
+    fn text(&self) -> String {
+        let mut text = format!("version\t{}\n", self.version);
+        let mut last: Vec<_> = self.last.iter().collect();
+        last.sort_by_key(|&(pack, _)| pack);
+        for (pack, file) in last {
+            match &file.line {
+                Some(line) => text += line,
+                None => text += &format!("last\t{}\t{}", pack, file.value),
+            }
+            text.push('\n');
+        }
+        let mut best: Vec<_> = self.best.iter().collect();
+        best.sort_by_key(|&(key, _)| key);
+        for ((pack, file), stats) in best {
+            match &stats.line {
+                Some(line) => text += line,
+                None => text += &format!("best\t{}\t{}\t{}\t{}", pack, file, stats.value.moves, stats.value.pushes),
+            }
+            text.push('\n');
+        }
+        for line in &self.unknown {
+            text += line;
+            text.push('\n');
+        }
+        text
+    }
+
+    fn write(&self) -> io::Result<()> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => { return Ok(()); },
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, self.text())
+    }
+
+    fn persist(&self) {
+        if let Err(e) = self.write() {
+            eprintln!("Couldn't write save file: {}", e);
+        }
+    }
+
+    pub fn completed_levels(&self, pack: &Pack) -> Vec<usize> {
+        pack.levels.iter().enumerate()
+            .filter(|(_, level)| self.best.contains_key(&(pack.title.clone(), level.file.clone())))
+            .map(|(idx, _)| idx)
+            .collect()
+    }
+
+    pub fn last_level(&self, pack: &Pack) -> usize {
+        self.last.get(&pack.title)
+            .and_then(|file| pack.levels.iter().position(|level| level.file == file.value))
+            .unwrap_or(0)
+    }
+
+    pub fn select(&mut self, pack: &Pack, level_id: usize) {
+        let file = &pack.levels[level_id].file;
+        if self.last.get(&pack.title).map(|last| &last.value) != Some(file) {
+            self.last.insert(pack.title.clone(), Record { value: file.clone(), line: None });
+        }
+        self.persist();
+    }
+
+    /// Record a win, keeping whichever run took the fewest moves (then the fewest pushes).
+    pub fn complete(&mut self, pack: &Pack, level_id: usize, stats: Stats) {
+        let key = (pack.title.clone(), pack.levels[level_id].file.clone());
+        let best = self.best.entry(key).or_insert(Record { value: stats, line: None });
+        if (stats.moves, stats.pushes) < (best.value.moves, best.value.pushes) {
+            *best = Record { value: stats, line: None };
+        }
+        self.persist();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_back_what_it_read() {
+        let text = "version\t1\nlast\tColorways\tlevel03.skb\nbest\tColorways\tlevel01.skb\t10\t2\n";
+        assert_eq!(Save::parse(text).text(), text);
+    }
+
+    #[test]
+    fn keeps_what_a_newer_version_saved() {
+        let text = "version\t3\nlast\tColorways\tlevel03.skb\t7\nbest\tColorways\tlevel01.skb\t10\t2\t41.5\nstars\t3\n";
+        assert_eq!(Save::parse(text).text(), text);
+    }
+
+    #[test]
+    fn rewrites_records_once_they_change() {
+        let mut save = Save::parse("version\t1\nbest\tColorways\tlevel01.skb\t10\t2\t41.5\n");
+        let key = ("Colorways".to_string(), "level01.skb".to_string());
+        save.best.insert(key, Record { value: Stats { moves: 9, pushes: 2 }, line: None });
+        assert_eq!(save.text(), "version\t1\nbest\tColorways\tlevel01.skb\t9\t2\n");
+    }
+
+    #[test]
+    fn never_lowers_the_version() {
+        assert_eq!(Save::parse("version\t0\n").version, VERSION);
+        assert_eq!(Save::parse("version\tbogus\n").version, VERSION);
+        assert_eq!(Save::parse("").text(), format!("version\t{}\n", VERSION));
+    }
+}
@@ -0,0 +1,194 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use crate::direction::Direction;
+use crate::puzzle::{Kind, Overlap, Puzzle, StateKey};
+
+// Finds a shortest win, counting moves the same way `Stats::moves` does.
+//
+// Searching every position outright is hopeless on the bigger levels: most of them differ only
+// in where the player is standing. Instead this searches between *events* (pushes, light
+// changes, sinkings, wins), with a plain walk between them found by a breadth-first search over
+// the tiles the player can reach without changing anything. A* over those events, costed by
+// walk length plus the event's own move, still finds the shortest solution: the player can't
+// get anywhere faster than walking there, so distance to the nearest exit never overestimates.
+// (Except when blocks eject the player, which can throw them several tiles in one move; that
+// rule gets no estimate at all, which makes the search a plain Dijkstra.)
+//
+// Everything is worked out by actually calling `Puzzle::step`, so the solver keeps up with the
+// rules without having to know any of them.
+
+/// How many distinct event positions to look at before giving up, by default.
+pub const DEFAULT_LIMIT: usize = 250_000;
+
+#[derive(Debug)]
+pub enum Solution {
+    /// A shortest sequence of moves that wins the level.
+    Solved(Vec<Direction>),
+    /// Every reachable position was tried, and none of them win.
+    Unsolvable,
+    /// Ran out of positions to try before finding either.
+    GaveUp { explored: usize },
+}
+
+struct Node {
+    cost: usize,
+    parent: Option<usize>,
+    /// The moves that lead here from the parent: a walk, then the event itself.
+    moves: Vec<Direction>,
+    puzzle: Option<Puzzle>,
+}
+
+/// How the walk reached each tile: the tile before it and the step taken from there.
+type Trail = HashMap<(i32, i32), Option<((i32, i32), Direction)>>;
+
+/// Each move out of `puzzle` that changes something besides where the player stands,
+/// along with the walk leading up to it and the position it leaves behind.
+fn events(puzzle: &Puzzle) -> Vec<(Vec<Direction>, Puzzle, bool)> {
+    let mut events = Vec::new();
+    let mut trail: Trail = HashMap::new();
+    let mut queue = VecDeque::new();
+    trail.insert(puzzle.player, None);
+    queue.push_back(puzzle.clone());
+
+    let walk_to = |trail: &Trail, mut at: (i32, i32)| {
+        let mut walk = Vec::new();
+        while let Some(Some((prev, direction))) = trail.get(&at) {
+            walk.push(direction.clone());
+            at = *prev;
+        }
+        walk.reverse();
+        walk
+    };
+
+    while let Some(here) = queue.pop_front() {
+        for direction in Direction::ALL.iter() {
+            let mut next = here.clone();
+            let outcome = next.step(direction);
+            if !outcome.is_move() { continue; }
+            let won = outcome.is_win();
+            let mut moved = here.clone();
+            moved.player = next.player;
+            if !won && moved.key() == next.key() {
+                if trail.contains_key(&next.player) { continue; }
+                trail.insert(next.player, Some((here.player, direction.clone())));
+                queue.push_back(next);
+            } else {
+                let mut moves = walk_to(&trail, here.player);
+                moves.push(direction.clone());
+                events.push((moves, next, won));
+            }
+        }
+    }
+    events
+}
+
+/// A lower bound on the moves left: walking straight to the nearest exit.
+fn estimate(puzzle: &Puzzle) -> usize {
+    if puzzle.rules.overlap == Overlap::Eject { return 0; }
+    let (x, y) = puzzle.player;
+    puzzle.pieces.iter()
+        .filter(|p| p.kind == Kind::Exit)
+        .map(|p| ((p.x - x).abs() + (p.y - y).abs()) as usize)
+        .min()
+        .unwrap_or(0)
+}
+
+pub fn solve(puzzle: &Puzzle, limit: usize) -> Solution {
+    let mut nodes = vec![Node { cost: 0, parent: None, moves: Vec::new(), puzzle: Some(puzzle.clone()) }];
+    let mut index: HashMap<StateKey, usize> = HashMap::new();
+    let mut heap = BinaryHeap::new();
+    let mut best_win: Option<(usize, usize, Vec<Direction>)> = None;
+    index.insert(puzzle.key(), 0);
+    heap.push(Reverse((estimate(puzzle), 0, 0)));
+
+    while let Some(Reverse((bound, cost, idx))) = heap.pop() {
+        if best_win.as_ref().is_some_and(|(win_cost, _, _)| bound >= *win_cost) { break; }
+        if cost != nodes[idx].cost { continue; }
+        let puzzle = match nodes[idx].puzzle.take() {
+            Some(puzzle) => puzzle,
+            None => { continue; },
+        };
+        for (moves, next, won) in events(&puzzle) {
+            let next_cost = cost + moves.len();
+            if won {
+                if best_win.as_ref().map_or(true, |(win_cost, _, _)| next_cost < *win_cost) {
+                    best_win = Some((next_cost, idx, moves));
+                }
+                continue;
+            }
+            let key = next.key();
+            let bound = next_cost + estimate(&next);
+            match index.get(&key) {
+                Some(&other) if nodes[other].cost <= next_cost => (),
+                Some(&other) => {
+                    nodes[other] = Node { cost: next_cost, parent: Some(idx), moves, puzzle: Some(next) };
+                    heap.push(Reverse((bound, next_cost, other)));
+                },
+                None => {
+                    if nodes.len() >= limit { return Solution::GaveUp { explored: nodes.len() }; }
+                    nodes.push(Node { cost: next_cost, parent: Some(idx), moves, puzzle: Some(next) });
+                    index.insert(key, nodes.len() - 1);
+                    heap.push(Reverse((bound, next_cost, nodes.len() - 1)));
+                },
+            }
+        }
+    }
+
+    let (_, mut at, last) = match best_win {
+        Some(win) => win,
+        None => { return Solution::Unsolvable; },
+    };
+    let mut segments = vec![last];
+    loop {
+        let node = &mut nodes[at];
+        segments.push(std::mem::take(&mut node.moves));
+        match node.parent {
+            Some(parent) => { at = parent; },
+            None => break,
+        }
+    }
+    segments.reverse();
+    Solution::Solved(segments.concat())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pack::Pack;
+    use crate::room::Room;
+
+    fn level(text: &str) -> Puzzle {
+        Room::parse(text.as_bytes()).unwrap_or_else(|e| panic!("test level is malformed: {}", e))
+    }
+
+    fn par(puzzle: &Puzzle) -> usize {
+        match solve(puzzle, DEFAULT_LIMIT) {
+            Solution::Solved(moves) => moves.len(),
+            other => panic!("expected a solution, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn walks_straight_out() {
+        assert_eq!(par(&level("W\n######\n#a..z#\n######\n")), 3);
+    }
+
+    #[test]
+    fn matches_the_first_levels_par() {
+        assert_eq!(par(&Pack::builtin().puzzle(0)), 10);
+    }
+
+    #[test]
+    fn knows_a_block_in_a_corner_is_stuck() {
+        let puzzle = level("W\n#####\n#ak.#\n#####\n");
+        assert!(matches!(solve(&puzzle, DEFAULT_LIMIT), Solution::Unsolvable));
+    }
+
+    #[test]
+    fn makes_no_estimate_when_blocks_eject_the_player() {
+        let mut puzzle = level("W\n######\n#a..z#\n######\n");
+        assert_eq!(estimate(&puzzle), 3);
+        puzzle.rules.overlap = Overlap::Eject;
+        assert_eq!(estimate(&puzzle), 0);
+    }
+}
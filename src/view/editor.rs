@@ -0,0 +1,377 @@
+use std::path::{Path, PathBuf};
+use opengl_graphics::GlGraphics;
+use opengl_graphics::Texture as GlTexture;
+use piston_window::{Context, DrawState, Rectangle, Transformed, UpdateArgs};
+use crate::app::{HeldKeys, Input};
+use crate::entity::{Entity, Player};
+use crate::puzzle::{Piece, Puzzle};
+use crate::room::{kind_from_chr, Room, Tile};
+use crate::view::{entities_for, tiles, Transition};
+
+const DISPLAY_WIDTH: f64 = 200.;
+const DISPLAY_HEIGHT: f64 = 200.;
+const DISPLAY_WIDTH_HALF: i64 = DISPLAY_WIDTH as i64 / 2;
+const DISPLAY_HEIGHT_HALF: i64 = DISPLAY_HEIGHT as i64 / 2;
+const TILE_SIZE: f64 = 16.;
+const CURSOR_COLOR: [f32; 4] = [1., 1., 1., 1.];
+const BRUSH_BACKGROUND: [f32; 4] = [0.2, 0.2, 0.2, 1.];
+const BRUSH_DEST: [f64; 4] = [2., 2., 20., 20.];
+
+// Everything that can be painted, in the order Q and E cycle through them.
+const BRUSHES: &[u8] = b"#.=!@$^&*%azkrgbycmwRGB7891234560DEFHIJOdefhijx:TUVXYZ/\\~_o";
+const HEADERS: &[u8] = b"RGBYCMW";
+const NEW_LEVEL_WIDTH: usize = 16;
+const NEW_LEVEL_HEIGHT: usize = 12;
+
+/// A level mid-edit, kept as the glyphs of its `.skb` file.
+#[derive(Clone)]
+struct Draft {
+    header: u8,
+    // Whatever follows the light color in the header, which the editor leaves alone.
+    rules: Vec<u8>,
+    rows: Vec<Vec<u8>>,
+}
+
+impl Draft {
+    /// An empty walled room, with somewhere for the player to start.
+    fn blank() -> Self {
+        let mut rows = vec![vec![b'#'; NEW_LEVEL_WIDTH]; NEW_LEVEL_HEIGHT];
+        for row in &mut rows[1..NEW_LEVEL_HEIGHT - 1] {
+            for glyph in &mut row[1..NEW_LEVEL_WIDTH - 1] {
+                *glyph = b'.';
+            }
+        }
+        rows[1][1] = b'a';
+        Draft { header: b'R', rules: Vec::new(), rows }
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Self {
+        let mut lines = bytes.split(|&c| c == b'\n')
+            .map(|line| line.strip_suffix(b"\r").unwrap_or(line));
+        let header_line = lines.next().unwrap_or_default();
+        let header = header_line.first().copied().unwrap_or(b'R');
+        let rules = header_line.get(1..).unwrap_or_default().to_vec();
+        let mut rows: Vec<_> = lines.map(|line| line.to_vec()).collect();
+        if rows.last().is_some_and(|row| row.is_empty()) { rows.pop(); }
+        Draft { header, rules, rows }
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![self.header];
+        bytes.extend_from_slice(&self.rules);
+        bytes.push(b'\n');
+        for row in &self.rows {
+            bytes.extend_from_slice(row);
+            bytes.push(b'\n');
+        }
+        bytes
+    }
+
+    fn glyph_at(&self, x: i32, y: i32) -> Option<u8> {
+        if x < 0 || y < 0 { return None; }
+        self.rows.get(y as usize)?.get(x as usize).copied()
+    }
+
+    /// Paint a glyph onto a tile, returning whether anything changed.
+    /// There's always exactly one starting position: placing it moves it, and it can't be painted over.
+    fn paint(&mut self, x: i32, y: i32, glyph: u8) -> bool {
+        let old = match self.glyph_at(x, y) {
+            None | Some(b'a' | b'+') => { return false; },
+            Some(old) => old,
+        };
+        // The player and gray blocks stand on a gray goal without covering it up.
+        let glyph = match (old, glyph) {
+            (b':' | b';', b'a') => b'+',
+            (b':' | b';', b'k') => b';',
+            _ => glyph,
+        };
+        // Nothing else has a glyph for standing on a goal, so it'd wipe the goal out. Only a
+        // tile can replace one.
+        let on_goal = old == b';' || matches!(Tile::from_chr(old as char), Some(Tile::Goal(_)));
+        if on_goal && !matches!(glyph, b'+' | b';') && Tile::from_chr(glyph as char).is_none() {
+            return false;
+        }
+        if old == glyph { return false; }
+        if glyph == b'a' || glyph == b'+' {
+            for row in self.rows.iter_mut() {
+                for old in row.iter_mut() {
+                    match *old {
+                        b'a' => { *old = b'.'; },
+                        b'+' => { *old = b':'; },
+                        _ => (),
+                    }
+                }
+            }
+        }
+        self.rows[y as usize][x as usize] = glyph;
+        true
+    }
+}
+
+pub struct EditorView {
+    texture: GlTexture,
+    path: PathBuf,
+    draft: Draft,
+    puzzle: Puzzle,
+    entities: Vec<Entity>,
+    player: Player,
+    cursor: Player,
+    brush: usize,
+    history: Vec<Draft>,
+    future: Vec<Draft>,
+    unsaved: bool,
+    confirm_leave: bool,
+}
+
+impl EditorView {
+    /// Edit the level at `path`, or start a new one there if there's no such file yet.
+    pub fn open(path: PathBuf) -> Result<Self, Box<dyn std::error::Error>> {
+        let draft = match std::fs::read(&path) {
+            Ok(bytes) => {
+                // Only open levels that load, so everything painted afterwards stays loadable.
+                Room::parse(&bytes)?;
+                Draft::from_bytes(&bytes)
+            },
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Draft::blank(),
+            Err(e) => { return Err(e.into()); },
+        };
+        let puzzle = preview(&draft);
+        let (x, y) = puzzle.player;
+        let mut editor = EditorView {
+            texture: crate::app::load_texture(),
+            path,
+            draft,
+            puzzle,
+            entities: Vec::new(),
+            player: Player::new(x, y),
+            cursor: Player::new_cursor(x, y, TILE_SIZE, TILE_SIZE),
+            brush: 0,
+            history: Vec::new(),
+            future: Vec::new(),
+            unsaved: false,
+            confirm_leave: false,
+        };
+        editor.refresh();
+        Ok(editor)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// What the window should be called while editing.
+    pub fn title(&self) -> String {
+        let name = self.path.display();
+        if self.confirm_leave {
+            format!("Editing {} - unsaved changes, press Backspace again to discard them", name)
+        } else if self.unsaved {
+            format!("Editing {} (unsaved)", name)
+        } else {
+            format!("Editing {}", name)
+        }
+    }
+
+    /// Rebuild the preview after the draft changes.
+    fn refresh(&mut self) {
+        self.puzzle = preview(&self.draft);
+        self.entities = entities_for(&self.puzzle);
+        for entity in self.entities.iter_mut() {
+            if let Entity::Lightbulb(bulb) = entity {
+                if self.puzzle.light_color.contains(&bulb.color) { bulb.snap_on(); }
+            }
+        }
+        let (x, y) = self.puzzle.player;
+        self.player.place(x, y);
+    }
+
+    fn edit(&mut self, change: impl FnOnce(&mut Draft) -> bool) {
+        let before = self.draft.clone();
+        if !change(&mut self.draft) { return; }
+        self.history.push(before);
+        self.future.clear();
+        self.unsaved = true;
+        self.refresh();
+    }
+
+    fn save(&mut self) {
+        let bytes = self.draft.to_bytes();
+        if let Err(e) = std::fs::write(&self.path, &bytes) {
+            eprintln!("Couldn't save {}: {}", self.path.display(), e);
+            return;
+        }
+        self.unsaved = false;
+        if let Err(e) = Room::from_file(&bytes) {
+            eprintln!("Saved {}, but it isn't playable yet: {}", self.path.display(), e);
+        }
+    }
+
+    fn camera_context(&self) -> Context {
+        let (x, y) = self.cursor.center();
+        let mut xs = [DISPLAY_WIDTH_HALF, x, self.puzzle.room.pixel_width() - DISPLAY_WIDTH_HALF];
+        let mut ys = [DISPLAY_HEIGHT_HALF, y, self.puzzle.room.pixel_height() - DISPLAY_HEIGHT_HALF];
+        xs.sort();
+        ys.sort();
+        Context::new_abs(DISPLAY_WIDTH, DISPLAY_HEIGHT)
+            .trans(-xs[1] as f64 + DISPLAY_WIDTH / 2., -ys[1] as f64 + DISPLAY_HEIGHT / 2.)
+    }
+
+    pub fn render(&self, gl: &mut GlGraphics) {
+        let context = self.camera_context();
+        tiles::render_room(
+            &self.puzzle.room,
+            &self.texture,
+            &DrawState::default(),
+            &context,
+            gl,
+        );
+        for entity in &self.entities {
+            entity.sprite().draw(&self.texture, &DrawState::default(), context.transform, gl);
+        }
+        self.player.sprite().draw(&self.texture, &DrawState::default(), context.transform, gl);
+        for entity in &self.entities {
+            if let Entity::Lightbulb(bulb) = entity { bulb.draw_light(&context, gl); }
+        }
+
+        let (x, y) = self.cursor.center();
+        let half = TILE_SIZE / 2.;
+        Rectangle::new_border(CURSOR_COLOR, 0.5).draw(
+            [x as f64 - half, y as f64 - half, TILE_SIZE, TILE_SIZE],
+            &DrawState::default(),
+            context.transform,
+            gl,
+        );
+
+        // The current brush, up in the corner.
+        let abs_context = Context::new_abs(DISPLAY_WIDTH, DISPLAY_HEIGHT);
+        Rectangle::new(BRUSH_BACKGROUND).draw(BRUSH_DEST, &DrawState::default(), abs_context.transform, gl);
+        let brush_transform = abs_context.trans(BRUSH_DEST[0] + 2., BRUSH_DEST[1] + 2.).transform;
+        let glyph = BRUSHES[self.brush] as char;
+        match glyph {
+            'a' => Player::new(0, 0).sprite().draw(&self.texture, &DrawState::default(), brush_transform, gl),
+            _ => if let Some(tile) = Tile::from_chr(glyph) {
+                tiles::draw(&tile, 0, 0, &self.texture, &DrawState::default(), brush_transform, gl);
+            } else if let Some(kind) = kind_from_chr(glyph) {
+                Entity::from_piece(&Piece { x: 0, y: 0, kind }, self.puzzle.lighting())
+                    .sprite()
+                    .draw(&self.texture, &DrawState::default(), brush_transform, gl);
+            },
+        }
+    }
+
+    pub fn update(&mut self, args: &UpdateArgs, held_keys: &mut HeldKeys) -> Option<Transition> {
+        self.cursor.update(args);
+        for entity in self.entities.iter_mut() {
+            entity.update(args);
+        }
+        for input in held_keys.inputs() {
+            if !matches!(input, Input::Reject) { self.confirm_leave = false; }
+            match input {
+                Input::Navigate(direction) => {
+                    let (nx, ny) = direction.from(self.cursor.x, self.cursor.y);
+                    if self.cursor.can_walk() && self.draft.glyph_at(nx, ny).is_some() {
+                        self.cursor.walk(&direction);
+                    }
+                },
+                Input::Accept => {
+                    let (x, y, glyph) = (self.cursor.x, self.cursor.y, BRUSHES[self.brush]);
+                    self.edit(|draft| draft.paint(x, y, glyph));
+                },
+                Input::PreviousBrush => { self.brush = (self.brush + BRUSHES.len() - 1) % BRUSHES.len(); },
+                Input::NextBrush => { self.brush = (self.brush + 1) % BRUSHES.len(); },
+                Input::CycleLight => {
+                    self.edit(|draft| {
+                        let idx = HEADERS.iter().position(|&h| h == draft.header).unwrap_or(0);
+                        draft.header = HEADERS[(idx + 1) % HEADERS.len()];
+                        true
+                    });
+                },
+                Input::Undo => {
+                    if let Some(draft) = self.history.pop() {
+                        self.future.push(std::mem::replace(&mut self.draft, draft));
+                        self.unsaved = true;
+                        self.refresh();
+                    }
+                },
+                Input::Redo => {
+                    if let Some(draft) = self.future.pop() {
+                        self.history.push(std::mem::replace(&mut self.draft, draft));
+                        self.unsaved = true;
+                        self.refresh();
+                    }
+                },
+                Input::Save => { self.save(); },
+                Input::Playtest => { return Some(Transition::Playtest(self.puzzle.clone())); },
+                Input::Reject => {
+                    if !self.unsaved || self.confirm_leave { return Some(Transition::Title); }
+                    self.confirm_leave = true;
+                },
+                _ => (),
+            }
+        }
+        None
+    }
+}
+
+/// The draft as a playable puzzle.
+fn preview(draft: &Draft) -> Puzzle {
+    // Drafts only come from levels that loaded, and painting can't break one.
+    Room::parse(&draft.to_bytes())
+        .unwrap_or_else(|e| panic!("editor produced a malformed level: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn draft(rows: &[&str]) -> Draft {
+        Draft { header: b'W', rules: Vec::new(), rows: rows.iter().map(|row| row.as_bytes().to_vec()).collect() }
+    }
+
+    fn rows(draft: &Draft) -> Vec<String> {
+        draft.rows.iter().map(|row| String::from_utf8_lossy(row).into_owned()).collect()
+    }
+
+    #[test]
+    fn start_on_a_goal_cant_be_painted_over() {
+        let mut draft = draft(&["#####", "#+.:#", "#####"]);
+        assert!(!draft.paint(1, 1, b'#'));
+        assert_eq!(rows(&draft), ["#####", "#+.:#", "#####"]);
+    }
+
+    #[test]
+    fn moving_the_start_off_a_goal_leaves_the_goal() {
+        let mut draft = draft(&["#####", "#+..#", "#####"]);
+        assert!(draft.paint(3, 1, b'a'));
+        assert_eq!(rows(&draft), ["#####", "#:.a#", "#####"]);
+        assert!(Room::parse(&draft.to_bytes()).is_ok());
+    }
+
+    #[test]
+    fn moving_the_start_onto_a_goal_keeps_the_goal() {
+        let mut draft = draft(&["#####", "#a.:#", "#####"]);
+        assert!(draft.paint(3, 1, b'a'));
+        assert_eq!(rows(&draft), ["#####", "#..+#", "#####"]);
+        assert!(Room::parse(&draft.to_bytes()).is_ok());
+    }
+
+    #[test]
+    fn gray_block_on_a_goal_keeps_the_goal() {
+        let mut draft = draft(&["#####", "#a.:#", "#####"]);
+        assert!(draft.paint(3, 1, b'k'));
+        assert_eq!(rows(&draft), ["#####", "#a.;#", "#####"]);
+        assert!(!draft.paint(3, 1, b'k'));
+    }
+
+    #[test]
+    fn other_pieces_cant_cover_a_goal() {
+        let mut draft = draft(&["#######", "#a:;TZ#", "#######"]);
+        for (x, glyph) in [(2, b'r'), (3, b'r'), (4, b'k'), (5, b'a'), (2, b'/'), (4, b'~')] {
+            assert!(!draft.paint(x, 1, glyph));
+        }
+        assert_eq!(rows(&draft), ["#######", "#a:;TZ#", "#######"]);
+        // Tiles still replace them, goal and all.
+        assert!(draft.paint(3, 1, b'.'));
+        assert!(draft.paint(4, 1, b':'));
+        assert_eq!(rows(&draft), ["#######", "#a:.:Z#", "#######"]);
+    }
+}
@@ -1,12 +1,14 @@
+use std::collections::VecDeque;
 use opengl_graphics::GlGraphics;
 use opengl_graphics::Texture as GlTexture;
 use piston_window::{Context, DrawState, Image, UpdateArgs, Transformed};
-use piston_window::draw_state::Blend;
-use crate::app::{Direction, HeldKeys, Input, int_lerp};
+use crate::app::{HeldKeys, Input, int_lerp};
 use crate::color::Color;
+use crate::direction::Direction;
 use crate::entity::{Entity, Player};
-use crate::room::Room;
-use crate::view::Transition;
+use crate::lurd;
+use crate::puzzle::{GameAction, Outcome, Puzzle};
+use crate::view::{cast_shadows, entities_for, lock_exits, switch_lights, tiles, Transition};
 
 const DISPLAY_WIDTH: f64 = 200.;
 const DISPLAY_HEIGHT: f64 = 200.;
@@ -16,46 +18,60 @@ const DISPLAY_HEIGHT_HALF: i64 = DISPLAY_HEIGHT as i64 / 2;
 const LEVEL_COMPLETE_SRC: [f64; 4] = [128., 0., 128., 112.];
 const LEVEL_COMPLETE_START_DEST: [f64; 4] = [36., -112., 128., 112.];
 const LEVEL_COMPLETE_END_DEST: [f64; 4] = [36., 40., 128., 112.];
-
-#[derive(Debug)]
-pub enum GameAction {
-    Stop,
-    ColorChange(Color),
-    Win,
-    DestroyBoth(usize, usize),
-}
+const PAUSED_SRC: [f64; 4] = [0., 160., 128., 96.];
+const PAUSED_DEST: [f64; 4] = [36., 52., 128., 96.];
+const PAUSED_OPTIONS: i32 = 4;
+const FAILED_SRC: [f64; 4] = [128., 192., 128., 64.];
+const FAILED_START_DEST: [f64; 4] = [36., -64., 128., 64.];
+const FAILED_END_DEST: [f64; 4] = [36., 68., 128., 64.];
 
 pub enum State {
     Play,
+    Paused,
     Win(f64),
+    /// Caught inside a block; the player's squashed flat and has to undo or start over.
+    Failed(f64),
 }
 
 pub struct GameView {
     texture: GlTexture,
     player: Player,
-    room: Room,
+    puzzle: Puzzle,
+    initial: Puzzle,
     entities: Vec<Entity>,
-    light_color: Color,
+    history: Vec<Puzzle>,
+    future: Vec<Puzzle>,
+    /// Every move that led here, in LURD, one letter for each snapshot in `history`.
+    record: String,
+    /// The letters for the moves in `future`, most recently undone last.
+    undone: String,
+    /// Moves still to be played back, one at a time as the player finishes walking.
+    playback: VecDeque<Direction>,
     level_id: usize,
     cursor: Option<Player>,
     state: State,
 }
 
 impl GameView {
-    pub fn new(level_id: usize) -> Self {
-        let (room, player, entities, light_color) = Room::new(level_id);
-        let mut game = GameView {
+    pub fn new(level_id: usize, puzzle: Puzzle) -> Self {
+        let (x, y) = puzzle.player;
+        let mut entities = entities_for(&puzzle);
+        switch_lights(&mut entities, &Color::Gray, &puzzle.light_color);
+        GameView {
             texture: crate::app::load_texture(),
-            player,
-            room,
+            player: Player::new(x, y),
+            initial: puzzle.clone(),
+            puzzle,
             entities,
-            light_color: Color::Gray,
+            history: Vec::new(),
+            future: Vec::new(),
+            record: String::new(),
+            undone: String::new(),
+            playback: VecDeque::new(),
             level_id,
             cursor: None,
             state: State::Play,
-        };
-        game.set_light_color(light_color);
-        game
+        }
     }
 
     fn absolute_context(&self) -> Context {
@@ -73,8 +89,8 @@ impl GameView {
     // TODO: if the level's too small probably center it instead
     fn camera(&self) -> (i64, i64) {
         let (x, y) = self.player.center();
-        let mut xs = vec![DISPLAY_WIDTH_HALF, x, self.room.pixel_width() - DISPLAY_WIDTH_HALF];
-        let mut ys = vec![DISPLAY_HEIGHT_HALF, y, self.room.pixel_height() - DISPLAY_HEIGHT_HALF];
+        let mut xs = [DISPLAY_WIDTH_HALF, x, self.puzzle.room.pixel_width() - DISPLAY_WIDTH_HALF];
+        let mut ys = [DISPLAY_HEIGHT_HALF, y, self.puzzle.room.pixel_height() - DISPLAY_HEIGHT_HALF];
         xs.sort();
         ys.sort();
         (xs[1], ys[1])
@@ -86,10 +102,6 @@ impl GameView {
             else { None }
         }).collect();
 
-        // for light in &lights {
-        //     light.draw_light_base(context, gl);
-        // }
-
         for light in &lights {
             light.draw_light(context, gl);
         }
@@ -100,7 +112,8 @@ impl GameView {
         let context = self.camera_context();
 
         // Action
-        self.room.render(
+        tiles::render_room(
+            &self.puzzle.room,
             &self.texture,
             &DrawState::default(),
             &context,
@@ -116,7 +129,16 @@ impl GameView {
             );
         }
 
-        self.player.sprite().draw(
+        let mut player = self.player.sprite();
+        if let State::Failed(progress) = self.state {
+            // Flatten towards the floor, keeping the feet where they are.
+            if let Some([x, y, w, h]) = player.rectangle {
+                let squashed = h * (1. - 0.75 * progress);
+                player.rectangle = Some([x, y + h - squashed, w, squashed]);
+            }
+            player.color = Some([1., 1. - 0.6 * progress as f32, 1. - 0.6 * progress as f32, 1.]);
+        }
+        player.draw(
             &self.texture,
             &DrawState::default(),
             context.transform,
@@ -151,6 +173,49 @@ impl GameView {
                 );
             }
         }
+
+        if let State::Failed(progress) = self.state {
+            let abs_context = self.absolute_context();
+            let dest = int_lerp(FAILED_START_DEST, FAILED_END_DEST, progress);
+            Image::new()
+                .src_rect(FAILED_SRC)
+                .rect(dest)
+                .draw(
+                    &self.texture,
+                    &DrawState::default(),
+                    abs_context.transform,
+                    gl,
+                );
+            if let Some(cursor) = &self.cursor {
+                cursor.sprite().draw(
+                    &self.texture,
+                    &DrawState::default(),
+                    abs_context.trans(46., 94.).transform,
+                    gl,
+                );
+            }
+        }
+
+        if let State::Paused = self.state {
+            let abs_context = self.absolute_context();
+            Image::new()
+                .src_rect(PAUSED_SRC)
+                .rect(PAUSED_DEST)
+                .draw(
+                    &self.texture,
+                    &DrawState::default(),
+                    abs_context.transform,
+                    gl,
+                );
+            if let Some(cursor) = &self.cursor {
+                cursor.sprite().draw(
+                    &self.texture,
+                    &DrawState::default(),
+                    abs_context.trans(46., 78.).transform,
+                    gl,
+                );
+            }
+        }
     }
 
     pub fn update(&mut self, args: &UpdateArgs, held_keys: &mut HeldKeys) -> Option<Transition> {
@@ -160,9 +225,10 @@ impl GameView {
         }
         match &mut self.state {
             State::Play => self.update_play(args, held_keys),
+            State::Paused => self.update_paused(args, held_keys),
             State::Win(progress) => {
                 if *progress < 1. {
-                    *progress = *progress + args.dt * 5.;
+                    *progress += args.dt * 5.;
                     if *progress >= 1. {
                         *progress = 1.;
                         self.cursor = Some(Player::new(0, 0));
@@ -186,90 +252,233 @@ impl GameView {
                                     _ => (),
                                 }
                             },
+                            Input::Copy => return Some(Transition::Copy(self.record.clone())),
+                            Input::Paste => return Some(Transition::Paste),
                             _ => (),
                         }
                     }
                 }
                 None
-            }
+            },
+            State::Failed(progress) => {
+                if *progress < 1. {
+                    *progress += args.dt * 3.;
+                    if *progress >= 1. {
+                        *progress = 1.;
+                        self.cursor = Some(Player::new(0, 0));
+                    }
+                }
+                self.update_failed(args, held_keys)
+            },
         }
     }
 
     fn update_play(&mut self, _args: &UpdateArgs, held_keys: &mut HeldKeys) -> Option<Transition> {
-        let mut action = None;  // TODO: should probably be a vec? eh.
-        for input in held_keys.inputs() {
+        let inputs = held_keys.inputs();
+        // Playing moves back carries on until it runs out, or anything's pressed at all.
+        if !inputs.is_empty() {
+            self.playback.clear();
+        } else if self.player.can_walk() {
+            if let Some(direction) = self.playback.pop_front() {
+                self.player.face(&direction);
+                return self.step(&direction);
+            }
+        }
+        for input in inputs {
             match input {
                 Input::Navigate(direction) => {
                     self.player.face(&direction);
-                    let (nx, ny) = direction.from(self.player.x, self.player.y);
-                    if self.player.can_walk() && self.tile_is_passable(nx, ny) {
-                        if let Some(entity_id) = self.entity_id_at(nx, ny) {
-                            if let Some(approach_action) = self.entities[entity_id].is_approachable(&direction, self) {
-                                if matches!(approach_action, GameAction::Stop) { continue; }
-                                if let GameAction::DestroyBoth(idx1, _) = approach_action {
-                                    action = Some(GameAction::DestroyBoth(idx1, entity_id));
-                                    continue;
-                                }
-                            }
-                            // borrow checker shenanigans
-                            match &self.entities[entity_id] {
-                                Entity::Block(block)
-                                if self.tile_in_light(block.x, block.y, &block.color) => (),
-                                _ => { action = self.entities[entity_id].on_approach(&direction); },
-                            }
-                        }
-                        self.player.walk(&direction);
+                    if !self.player.can_walk() { continue; }
+                    if let Some(transition) = self.step(&direction) {
+                        return Some(transition);
                     }
                 },
                 Input::Reject => { return Some(Transition::Menu(self.level_id)); },
+                Input::Pause => {
+                    self.state = State::Paused;
+                    self.cursor = Some(Player::new(0, 0));
+                    return None;
+                },
+                Input::Restart => { self.restart(); },
+                Input::Undo => {
+                    if let Some(puzzle) = self.history.pop() {
+                        let current = self.restore(puzzle);
+                        self.future.push(current);
+                        self.undone.extend(self.record.pop());
+                    }
+                },
+                Input::Redo => {
+                    if let Some(puzzle) = self.future.pop() {
+                        let current = self.restore(puzzle);
+                        self.history.push(current);
+                        self.record.extend(self.undone.pop());
+                    }
+                },
+                Input::Copy => { return Some(Transition::Copy(self.record.clone())); },
+                Input::Paste => { return Some(Transition::Paste); },
                 _ => (),
             }
         }
-        if let Some(action) = action {
-            match action {
-                GameAction::ColorChange(color) => { self.set_light_color(color); },
-                GameAction::Win => {
-                    self.state = State::Win(0.);
-                    return Some(Transition::Win(self.level_id));
+        None
+    }
+
+    fn update_paused(&mut self, args: &UpdateArgs, held_keys: &mut HeldKeys) -> Option<Transition> {
+        let cursor = self.cursor.as_mut()?;
+        cursor.update(args);
+        let mut choice = None;
+        for input in held_keys.inputs() {
+            if !cursor.can_walk() { break; }
+            match input {
+                Input::Navigate(direction @ Direction::North) if cursor.y > 0 => {
+                    cursor.walk(&direction);
                 },
-                GameAction::DestroyBoth(idx1, idx2) => {
-                    let mut idx = 0;
-                    self.entities.retain(|_| { let m = idx1 != idx && idx2 != idx; idx += 1; m });
+                Input::Navigate(direction @ Direction::South) if cursor.y < PAUSED_OPTIONS - 1 => {
+                    cursor.walk(&direction);
                 },
-                GameAction::Stop => (),
+                Input::Pause | Input::Reject => { choice = Some(0); break; },
+                Input::Restart => { choice = Some(1); break; },
+                Input::Accept => { choice = Some(cursor.y); break; },
+                Input::Copy => { return Some(Transition::Copy(self.record.clone())); },
+                Input::Paste => { return Some(Transition::Paste); },
+                _ => (),
             }
         }
+        match choice? {
+            0 => self.resume(),
+            1 => self.restart(),
+            2 => return Some(Transition::Menu(self.level_id)),
+            3 => return Some(Transition::Title),
+            _ => (),
+        }
         None
     }
 
-    pub fn set_light_color(&mut self, color: Color) {
-        if self.light_color == color { return; }
-        for entity in self.entities.iter_mut() {
-            if let Entity::Lightbulb(bulb) = entity {
-                if bulb.color == self.light_color { bulb.turn_off(); }
-                else if bulb.color == color { bulb.turn_on(); }
+    fn update_failed(&mut self, args: &UpdateArgs, held_keys: &mut HeldKeys) -> Option<Transition> {
+        let cursor = self.cursor.as_mut()?;
+        cursor.update(args);
+        let mut choice = None;
+        for input in held_keys.inputs() {
+            if !cursor.can_walk() { break; }
+            match input {
+                Input::Navigate(direction @ Direction::North) if cursor.y == 1 => {
+                    cursor.walk(&direction);
+                },
+                Input::Navigate(direction @ Direction::South) if cursor.y == 0 => {
+                    cursor.walk(&direction);
+                },
+                Input::Undo => { choice = Some(0); break; },
+                Input::Restart => { choice = Some(1); break; },
+                Input::Accept => { choice = Some(cursor.y); break; },
+                Input::Reject => { return Some(Transition::Menu(self.level_id)); },
+                Input::Copy => { return Some(Transition::Copy(self.record.clone())); },
+                Input::Paste => { return Some(Transition::Paste); },
+                _ => (),
             }
         }
-        self.light_color = color;
+        match choice? {
+            0 => {
+                self.resume();
+                if let Some(puzzle) = self.history.pop() {
+                    self.restore(puzzle);
+                    self.record.pop();
+                }
+            },
+            1 => self.restart(),
+            _ => (),
+        }
+        None
+    }
+
+    fn resume(&mut self) {
+        self.state = State::Play;
+        self.cursor = None;
+    }
+
+    /// Put the level back how it started, forgetting the move history.
+    fn restart(&mut self) {
+        self.resume();
+        self.history.clear();
+        self.future.clear();
+        self.record.clear();
+        self.undone.clear();
+        self.playback.clear();
+        self.restore(self.initial.clone());
     }
 
-    pub fn tile_is_passable(&self, x: i32, y: i32) -> bool {
-        let tile = self.room.tile_at(x, y);
-        tile.map_or(false, |tile| tile.is_passable())
+    /// Start the level over and play back moves written in LURD, as if they were being pressed.
+    pub fn play_back(&mut self, text: &str) {
+        match lurd::parse(text) {
+            Ok(moves) => {
+                self.restart();
+                self.playback = moves.into();
+            },
+            Err(e) => eprintln!("Couldn't play back those moves: {}", e),
+        }
     }
 
-    pub fn tile_in_light(&self, x: i32, y: i32, color: &Color) -> bool {
-        if color == &Color::White { return true; }
-        color.contains(&self.light_color) && self.room.tile_in_light(x, y, &self.light_color)
+    /// Take a step, and if anything came of it, remember it and set it moving.
+    fn step(&mut self, direction: &Direction) -> Option<Transition> {
+        let before = self.puzzle.clone();
+        let outcome = self.puzzle.step(direction);
+        if !outcome.is_move() { return None; }
+        let old_color = before.light_color.clone();
+        self.history.push(before);
+        self.record.push(lurd::letter(direction, outcome.is_push()));
+        self.future.clear();
+        self.undone.clear();
+        self.animate(direction, &old_color, outcome)
     }
 
-    pub fn entity_id_at(&self, x: i32, y: i32) -> Option<usize> {
-        self.entities.iter()
-            .position(|e| e.x() == x && e.y() == y)
+    /// Swap in an earlier (or later) snapshot of the puzzle, returning the current one.
+    /// Everything comes back at rest, so nothing snaps partway through an animation;
+    /// only the lights animate over to the restored color.
+    fn restore(&mut self, puzzle: Puzzle) -> Puzzle {
+        let current = std::mem::replace(&mut self.puzzle, puzzle);
+        self.entities = entities_for(&self.puzzle);
+        for entity in self.entities.iter_mut() {
+            if let Entity::Lightbulb(bulb) = entity {
+                if current.light_color.contains(&bulb.color) { bulb.snap_on(); }
+            }
+        }
+        switch_lights(&mut self.entities, &current.light_color, &self.puzzle.light_color);
+        let (x, y) = self.puzzle.player;
+        self.player.place(x, y);
+        current
     }
 
-    pub fn entity_at(&self, x: i32, y: i32) -> Option<&Entity> {
-        let idx = self.entity_id_at(x, y)?;
-        Some(&self.entities[idx])
+    fn animate(&mut self, direction: &Direction, old_color: &Color, outcome: Outcome) -> Option<Transition> {
+        for &idx in &outcome.pushed {
+            self.entities[idx].push(direction);
+        }
+        if outcome.walked {
+            self.player.walk(direction);
+        }
+        for action in &outcome.actions {
+            match action {
+                GameAction::Recolor(idx, color) => {
+                    if let Entity::Block(block) = &mut self.entities[*idx] { block.color = color.clone(); }
+                },
+                GameAction::Eject(x, y) => { self.player.place(*x, *y); },
+                GameAction::Crush => {
+                    self.state = State::Failed(0.);
+                    self.playback.clear();
+                },
+                GameAction::ColorChange(_) | GameAction::Win | GameAction::Remove(_) => (),
+            }
+        }
+        let removed: Vec<_> = outcome.removed().collect();
+        let mut idx = 0;
+        self.entities.retain(|_| { let keep = !removed.contains(&idx); idx += 1; keep });
+        if outcome.is_rearranged() {
+            cast_shadows(&mut self.entities, &self.puzzle);
+            lock_exits(&mut self.entities, &self.puzzle);
+        }
+        // However many times the light changed along the way, only where it ended up matters.
+        switch_lights(&mut self.entities, old_color, &self.puzzle.light_color);
+        if outcome.is_win() {
+            self.state = State::Win(0.);
+            return Some(Transition::Win(self.level_id, self.puzzle.stats));
+        }
+        None
     }
 }
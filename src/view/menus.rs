@@ -1,4 +1,5 @@
 use crate::app::{HeldKeys, Input};
+use crate::direction::Direction;
 use crate::entity::Player;
 use crate::view::Transition;
 use opengl_graphics::GlGraphics;
@@ -11,6 +12,7 @@ const DISPLAY_HEIGHT: f64 = 200.;
 const LEVEL_PADDING: f64 = 8.;
 const LEVELS_HORIZONTAL: usize = 4;
 const LEVELS_VERTICAL: usize = 2;
+const LEVELS_PER_PAGE: usize = LEVELS_HORIZONTAL * LEVELS_VERTICAL;
 const LEVEL_WIDTH: f64 = 30.;
 const LEVEL_HEIGHT: f64 = 25.;
 const LEVEL_OFFSET_X: f64 = 29.;
@@ -19,45 +21,105 @@ const LEVEL_SPACING_X: f64 = LEVEL_WIDTH + LEVEL_PADDING;
 const LEVEL_SPACING_Y: f64 = LEVEL_HEIGHT + LEVEL_PADDING;
 const INSTRUCTION_SRC: [f64; 4] = [192., 112., 64., 48.];
 const INSTRUCTION_DEST: [f64; 4] = [68., 100., 64., 48.];
+const PAGE_NUMBER_TOP: f64 = 80.;
+// "0" through "9", then "/"
+const DIGITS_SRC: [f64; 4] = [128., 160., 5., 7.];
+const DIGIT_SPACING: f64 = 6.;
 
 pub struct MenuView {
     texture: GlTexture,
     completed_levels: Vec<usize>,
+    num_levels: usize,
+    page: usize,
     cursor: Player,
 }
 
+/// Draw a string of digits and slashes with its top left corner at the given spot.
+fn draw_digits(text: &str, left: f64, top: f64, texture: &GlTexture, context: &Context, gl: &mut GlGraphics) {
+    for (i, chr) in text.chars().enumerate() {
+        let idx = chr.to_digit(10).unwrap_or(10) as f64;
+        let [src_x, src_y, width, height] = DIGITS_SRC;
+        Image::new()
+            .src_rect([src_x + idx * DIGIT_SPACING, src_y, width, height])
+            .rect([left + i as f64 * DIGIT_SPACING, top, width, height])
+            .draw(texture, &DrawState::default(), context.transform, gl);
+    }
+}
+
+fn digits_width(text: &str) -> f64 {
+    text.len() as f64 * DIGIT_SPACING - 1.
+}
+
 impl MenuView {
-    pub fn new(level: usize, completed_levels: Vec<usize>) -> Self {
+    pub fn new(level: usize, completed_levels: Vec<usize>, num_levels: usize) -> Self {
+        let page = level / LEVELS_PER_PAGE;
         let x = level % LEVELS_HORIZONTAL;
-        let y = level / LEVELS_HORIZONTAL;
+        let y = level % LEVELS_PER_PAGE / LEVELS_HORIZONTAL;
         Self {
             texture: crate::app::load_texture(),
             cursor: Player::new_cursor(x as i32, y as i32, LEVEL_SPACING_X, LEVEL_SPACING_Y),
             completed_levels,
+            num_levels,
+            page,
+        }
+    }
+
+    fn num_pages(&self) -> usize {
+        self.num_levels.div_ceil(LEVELS_PER_PAGE)
+    }
+
+    /// The level in a given spot on a given page, if there is one.
+    fn level_at(&self, page: usize, x: i32, y: i32) -> Option<usize> {
+        if x < 0 || x >= LEVELS_HORIZONTAL as i32 || y < 0 || y >= LEVELS_VERTICAL as i32 { return None; }
+        let level_id = page * LEVELS_PER_PAGE + y as usize * LEVELS_HORIZONTAL + x as usize;
+        if level_id < self.num_levels { Some(level_id) } else { None }
+    }
+
+    /// The level under the cursor.
+    pub fn selected(&self) -> usize {
+        self.level_at(self.page, self.cursor.x, self.cursor.y).unwrap_or(0)
+    }
+
+    /// Where the cursor ends up walking off the top or bottom of the page, if there's a page there.
+    fn turn_page(&self, direction: &Direction) -> Option<(usize, i32, i32)> {
+        let x = self.cursor.x;
+        match direction {
+            Direction::North if self.page > 0 => Some((self.page - 1, x, LEVELS_VERTICAL as i32 - 1)),
+            Direction::South if self.page + 1 < self.num_pages() => {
+                // The last page might not reach this far over.
+                let x = (0..=x).rev().find(|&x| self.level_at(self.page + 1, x, 0).is_some())?;
+                Some((self.page + 1, x, 0))
+            },
+            _ => None,
         }
     }
 
     pub fn render(&self, gl: &mut GlGraphics) {
         let context = Context::new_abs(DISPLAY_WIDTH, DISPLAY_HEIGHT);
         let color = Rectangle::new([0.7, 0.7, 0.7, 1.]);
-        for y in 0..LEVELS_VERTICAL {
-            for x in 0..LEVELS_HORIZONTAL {
-                let left = LEVEL_OFFSET_X + x as f64 * LEVEL_SPACING_X;
-                let top = LEVEL_OFFSET_Y + y as f64 * LEVEL_SPACING_Y;
-                let right = left + LEVEL_WIDTH;
-                let bottom = top + LEVEL_HEIGHT;
-                color.draw(
-                    rectangle_by_corners(left, top, right, bottom),
-                    &DrawState::default(),
-                    context.transform,
-                    gl,
-                );
-            }
+        let first = self.page * LEVELS_PER_PAGE;
+        let page_levels = first..self.num_levels.min(first + LEVELS_PER_PAGE);
+        for idx in page_levels.clone() {
+            let x = idx % LEVELS_HORIZONTAL;
+            let y = idx % LEVELS_PER_PAGE / LEVELS_HORIZONTAL;
+            let left = LEVEL_OFFSET_X + x as f64 * LEVEL_SPACING_X;
+            let top = LEVEL_OFFSET_Y + y as f64 * LEVEL_SPACING_Y;
+            let right = left + LEVEL_WIDTH;
+            let bottom = top + LEVEL_HEIGHT;
+            color.draw(
+                rectangle_by_corners(left, top, right, bottom),
+                &DrawState::default(),
+                context.transform,
+                gl,
+            );
+            // Tucked in the corner, clear of the cursor and the completion badge.
+            let number = (idx + 1).to_string();
+            draw_digits(&number, left + 2., top + 1., &self.texture, &context, gl);
         }
 
-        for idx in &self.completed_levels {
+        for idx in self.completed_levels.iter().filter(|idx| page_levels.contains(idx)) {
             let x = idx % LEVELS_HORIZONTAL;
-            let y = idx / LEVELS_HORIZONTAL;
+            let y = idx % LEVELS_PER_PAGE / LEVELS_HORIZONTAL;
             let left = LEVEL_OFFSET_X + x as f64 * LEVEL_SPACING_X + 20.;
             let top = LEVEL_OFFSET_Y + y as f64 * LEVEL_SPACING_Y - 5.;
             Image::new()
@@ -71,6 +133,12 @@ impl MenuView {
                 );
         }
 
+        if self.num_pages() > 1 {
+            let page_number = format!("{}/{}", self.page + 1, self.num_pages());
+            let left = ((DISPLAY_WIDTH - digits_width(&page_number)) / 2.).floor();
+            draw_digits(&page_number, left, PAGE_NUMBER_TOP, &self.texture, &context, gl);
+        }
+
         Image::new()
             .src_rect(INSTRUCTION_SRC)
             .rect(INSTRUCTION_DEST)
@@ -95,16 +163,20 @@ impl MenuView {
             match input {
                 Input::Navigate(direction) => {
                     self.cursor.face(&direction);
+                    if !self.cursor.can_walk() { continue; }
                     let (nx, ny) = direction.from(self.cursor.x, self.cursor.y);
-                    if self.cursor.can_walk()
-                            && nx >= 0 && nx < LEVELS_HORIZONTAL as i32
-                            && ny >= 0 && ny < LEVELS_VERTICAL as i32  {
+                    if self.level_at(self.page, nx, ny).is_some() {
+                        self.cursor.walk(&direction);
+                    } else if let Some((page, x, y)) = self.turn_page(&direction) {
+                        // Slide in from just off the edge of the new page.
+                        let (bx, by) = direction.from(x, y);
+                        self.page = page;
+                        self.cursor.place(2 * x - bx, 2 * y - by);
                         self.cursor.walk(&direction);
                     }
                 }
                 Input::Accept => {
-                    let level_id = self.cursor.y as usize * LEVELS_HORIZONTAL + self.cursor.x as usize;
-                    return Some(Transition::Game(level_id));
+                    return Some(Transition::Game(self.selected()));
                 }
                 _ => (),
             }
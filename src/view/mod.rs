@@ -1,42 +1,55 @@
 use opengl_graphics::GlGraphics;
 use piston_window::UpdateArgs;
 use crate::app::HeldKeys;
+use crate::color::Color;
+use crate::entity::Entity;
+use crate::puzzle::{Puzzle, Stats};
 
+pub mod editor;
 pub mod game;
 pub mod menus;
+pub mod tiles;
 pub mod title;
 
-pub use game::{GameView, GameAction};
+pub use editor::EditorView;
+pub use game::GameView;
 pub use menus::MenuView;
 pub use title::TitleView;
 
 pub enum Transition {
     Game(usize),
     Menu(usize),
-    Win(usize),  // this isn't a transition at all! Who's running this circus??
+    Title,
+    Win(usize, Stats),  // this isn't a transition at all! Who's running this circus??
+    Playtest(Puzzle),
+    // Nor are these. They're passed along to the window, which owns the clipboard.
+    Copy(String),
+    Paste,
 }
 
 pub enum View {
-    Game(GameView),
+    Editor(Box<EditorView>),
+    Game(Box<GameView>),
     Menu(MenuView),
     Title(TitleView),
 }
 
 impl View {
-    pub fn menu(level_id: usize, completed_levels: Vec<usize>) -> Self {
-        Self::Menu(MenuView::new(level_id, completed_levels))
+    pub fn menu(level_id: usize, completed_levels: Vec<usize>, num_levels: usize) -> Self {
+        Self::Menu(MenuView::new(level_id, completed_levels, num_levels))
     }
 
-    pub fn game(level_id: usize) -> Self {
-        Self::Game(GameView::new(level_id))
+    pub fn game(level_id: usize, puzzle: Puzzle) -> Self {
+        Self::Game(Box::new(GameView::new(level_id, puzzle)))
     }
 
-    pub fn title() -> Self {
-        Self::Title(TitleView::new())
+    pub fn title(last_level: usize) -> Self {
+        Self::Title(TitleView::new(last_level))
     }
 
     pub fn render(&self, gl: &mut GlGraphics) {
         match self {
+            View::Editor(v) => v.render(gl),
             View::Menu(v) => v.render(gl),
             View::Game(v) => v.render(gl),
             View::Title(v) => v.render(gl),
@@ -45,9 +58,51 @@ impl View {
 
     pub fn update(&mut self, args: &UpdateArgs, held_keys: &mut HeldKeys) -> Option<Transition> {
         match self {
+            View::Editor(v) => v.update(args, held_keys),
             View::Menu(v) => v.update(args, held_keys),
             View::Game(v) => v.update(args, held_keys),
             View::Title(v) => v.update(args, held_keys),
         }
     }
 }
+
+pub fn entities_for(puzzle: &Puzzle) -> Vec<Entity> {
+    let mut entities: Vec<_> = puzzle.pieces.iter()
+        .map(|piece| Entity::from_piece(piece, puzzle.lighting()))
+        .collect();
+    lock_exits(&mut entities, puzzle);
+    entities
+}
+
+/// Lock or unlock the exits, depending on whether the goals are all filled yet.
+pub fn lock_exits(entities: &mut [Entity], puzzle: &Puzzle) {
+    let locked = !puzzle.goals_filled();
+    for entity in entities.iter_mut() {
+        if let Entity::Exit(exit) = entity {
+            exit.locked = locked;
+        }
+    }
+}
+
+/// Fit every bulb's light to the shadows the puzzle's pieces cast now.
+pub fn cast_shadows(entities: &mut [Entity], puzzle: &Puzzle) {
+    for entity in entities.iter_mut() {
+        if let Entity::Lightbulb(bulb) = entity {
+            bulb.set_light(puzzle.lighting().fan(bulb.x, bulb.y).to_vec());
+        }
+    }
+}
+
+/// Kick off the bulb animations for a change from one mix of light to another.
+pub fn switch_lights(entities: &mut [Entity], from: &Color, to: &Color) {
+    if from == to { return; }
+    for entity in entities.iter_mut() {
+        if let Entity::Lightbulb(bulb) = entity {
+            match (from.contains(&bulb.color), to.contains(&bulb.color)) {
+                (true, false) => bulb.turn_off(),
+                (false, true) => bulb.turn_on(),
+                _ => (),
+            }
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use piston_window::{Context, DrawState, Image};
+use piston_window::math::Matrix2d;
+use opengl_graphics::GlGraphics;
+use opengl_graphics::Texture as GlTexture;
+use crate::room::{Room, Tile};
+
+const TILE_SIZE: f64 = 16.;
+const WALL: [f64; 4] = [32., 0., TILE_SIZE, TILE_SIZE];
+const FLOOR: [f64; 4] = [32., 16., TILE_SIZE, TILE_SIZE];
+const GLASS: [f64; 4] = [208., 176., TILE_SIZE, TILE_SIZE];
+const CURTAIN: [f64; 4] = [112., 16., TILE_SIZE, TILE_SIZE];
+const SUNKEN: [f64; 4] = [176., 176., TILE_SIZE, TILE_SIZE];
+const GOAL_MARK: [f64; 4] = [192., 176., TILE_SIZE, TILE_SIZE];
+
+pub fn sprite(tile: &Tile, x: usize, y: usize) -> Image {
+    let src = match tile {
+        Tile::Wall => WALL,
+        Tile::Floor | Tile::Goal(_) => FLOOR,
+        Tile::Glass | Tile::Filter(_) => GLASS,
+        Tile::Curtain => CURTAIN,
+        Tile::Sunken => SUNKEN,
+    };
+    let image = match tile {
+        Tile::Filter(color) => Image::new_color(color.as_component()),
+        _ => Image::new(),
+    };
+    image
+        .src_rect(src)
+        .rect([x as f64 * TILE_SIZE, y as f64 * TILE_SIZE, TILE_SIZE, TILE_SIZE])
+}
+
+/// Anything drawn over a tile's sprite, tinted separately from the tile underneath.
+fn overlay(tile: &Tile, x: usize, y: usize) -> Option<Image> {
+    match tile {
+        Tile::Goal(color) => Some(Image::new_color(color.as_component())
+            .src_rect(GOAL_MARK)
+            .rect([x as f64 * TILE_SIZE, y as f64 * TILE_SIZE, TILE_SIZE, TILE_SIZE])),
+        _ => None,
+    }
+}
+
+pub fn draw(tile: &Tile,
+            x: usize,
+            y: usize,
+            texture: &GlTexture,
+            draw_state: &DrawState,
+            transform: Matrix2d,
+            gl: &mut GlGraphics) {
+    sprite(tile, x, y).draw(texture, draw_state, transform, gl);
+    if let Some(image) = overlay(tile, x, y) {
+        image.draw(texture, draw_state, transform, gl);
+    }
+}
+
+pub fn render_room(room: &Room,
+                   texture: &GlTexture,
+                   draw_state: &DrawState,
+                   context: &Context,
+                   gl: &mut GlGraphics) {
+    let width = room.width();
+    for (i, elem) in room.tiles().iter().enumerate() {
+        let x = i % width;
+        let y = i / width;
+        draw(elem, x, y, texture, draw_state, context.transform, gl);
+    }
+}
@@ -1,11 +1,13 @@
 use opengl_graphics::GlGraphics;
 use opengl_graphics::Texture as GlTexture;
 use piston_window::{Context, DrawState, Image, Transformed, UpdateArgs};
-use crate::app::{Direction, HeldKeys, Input};
+use crate::app::{HeldKeys, Input};
 use crate::color::Color;
+use crate::direction::Direction;
 use crate::entity::{Entity, Player};
+use crate::puzzle::Puzzle;
 use crate::room::Room;
-use crate::view::Transition;
+use crate::view::{entities_for, switch_lights, tiles, Transition};
 
 const DISPLAY_WIDTH: f64 = 200.;
 const DISPLAY_HEIGHT: f64 = 200.;
@@ -34,23 +36,23 @@ enum State {
 pub struct TitleView {
     texture: GlTexture,
     cursor: Player,
-    room: Room,
+    puzzle: Puzzle,
     entities: Vec<Entity>,
-    light_color: Color,
+    last_level: usize,
     state: State,
 }
 
 impl TitleView {
-    pub fn new() -> Self {
-        let (room, _, entities, light_color) = Room::new_title();
-        let mut title = Self {
+    pub fn new(last_level: usize) -> Self {
+        let puzzle = Room::new_title();
+        let mut entities = entities_for(&puzzle);
+        switch_lights(&mut entities, &Color::Gray, &puzzle.light_color);
+        Self {
             texture: crate::app::load_texture(),
             cursor: Player::new_cursor(0, 0, 16., 16.),
-            room, entities, light_color: Color::Gray,
+            puzzle, entities, last_level,
             state: State::InputCheck,
-        };
-        title.set_light_color(light_color);
-        title
+        }
     }
 
     fn render_lights(&self, gl: &mut GlGraphics, context: &Context) {
@@ -67,7 +69,8 @@ impl TitleView {
     pub fn render(&self, gl: &mut GlGraphics) {
         let context = Context::new_abs(DISPLAY_WIDTH, DISPLAY_HEIGHT);
         let room_context = context.trans(ROOM_OFFSET_X, ROOM_OFFSET_Y);
-        self.room.render(
+        tiles::render_room(
+            &self.puzzle.room,
             &self.texture,
             &DrawState::default(),
             &room_context,
@@ -143,7 +146,7 @@ impl TitleView {
                 },
                 Input::Accept => match self.cursor.y {
                     0 => { return Some(Transition::Game(0)); },
-                    1 => { return Some(Transition::Menu(0)); },
+                    1 => { return Some(Transition::Menu(self.last_level)); },
                     _ => (),
                 },
                 _ => ()
@@ -160,13 +163,7 @@ impl TitleView {
     }
 
     pub fn set_light_color(&mut self, color: Color) {
-        if self.light_color == color { return; }
-        for entity in self.entities.iter_mut() {
-            if let Entity::Lightbulb(bulb) = entity {
-                if bulb.color == self.light_color { bulb.turn_off(); }
-                else if bulb.color == color { bulb.turn_on(); }
-            }
-        }
-        self.light_color = color;
+        switch_lights(&mut self.entities, &self.puzzle.light_color, &color);
+        self.puzzle.light_color = color;
     }
 }
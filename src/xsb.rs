@@ -0,0 +1,315 @@
+use std::fmt;
+use crate::color::Color;
+use crate::puzzle::{Kind, Puzzle};
+use crate::room::{LevelError, Room, Tile};
+
+// Converts between Colorways levels and XSB, the plain text format most Sokoban collections
+// come in:
+//
+//     #  wall        @  player          $  box           .  goal
+//     -  floor       +  player on goal  *  box on goal      (spaces and _ are floor too)
+//
+// A collection is any number of boards, with other lines of text between them. A `Title:` line
+// after a board names it; failing that, a line of text right before it does. Boards may also be
+// run-length encoded, with a count before a glyph (`4#` for `####`) and `|` between rows.
+//
+// Boxes come in as gray blocks and goals as gray goals, in a dark room with no exit, so the
+// level's won the Sokoban way, by covering every goal. Only levels made of those same few
+// things can go back out.
+
+const BOARD_GLYPHS: &str = "#@+$*.-_ |0123456789";
+/// The widest a board's row can get, so a huge run-length count can't eat all the memory.
+pub const MAX_WIDTH: usize = 1000;
+/// The most rows a board can have, for the same reason: `|` starts a row without a new line.
+pub const MAX_HEIGHT: usize = 1000;
+
+/// One level read out of an XSB collection, as the text of a `.skb` file.
+pub struct XsbLevel {
+    pub title: Option<String>,
+    pub source: String,
+}
+
+/// Why a level couldn't be converted. Lines and columns count from 1: for an import, within the
+/// XSB file; for an export, within the level file, the same as `LevelError`.
+#[derive(Debug, PartialEq)]
+pub enum XsbError {
+    /// A board that doesn't make a loadable level, such as one without a player.
+    Level { line: usize, error: LevelError },
+    /// Something plain Sokoban has no glyph for.
+    Unsupported { line: usize, column: usize, what: String },
+    /// A row that spells out to more than `MAX_WIDTH` tiles.
+    TooWide { line: usize },
+    /// A board that spells out to more than `MAX_HEIGHT` rows, by the time it gets to this line.
+    TooTall { line: usize },
+    Empty,
+}
+
+impl fmt::Display for XsbError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            XsbError::Level { line, error } => write!(f, "board starting on line {}: {}", line, error),
+            XsbError::Unsupported { line, column, what } => {
+                write!(f, "line {}, column {}: {} can't be written as XSB", line, column, what)
+            },
+            XsbError::TooWide { line } => write!(f, "line {}: row is more than {} tiles wide", line, MAX_WIDTH),
+            XsbError::TooTall { line } => write!(f, "line {}: board is more than {} rows tall", line, MAX_HEIGHT),
+            XsbError::Empty => write!(f, "no boards found"),
+        }
+    }
+}
+
+impl std::error::Error for XsbError {}
+
+fn is_board_line(line: &str) -> bool {
+    line.contains('#') && line.chars().all(|c| BOARD_GLYPHS.contains(c))
+}
+
+/// Spell out a run-length encoded line, which may hold several rows, onto the end of a board's
+/// rows so far.
+fn expand(line: &str, line_number: usize, rows: &mut Vec<String>) -> Result<(), XsbError> {
+    let too_wide = || XsbError::TooWide { line: line_number };
+    let new_row = |rows: &mut Vec<String>| {
+        if rows.len() >= MAX_HEIGHT { return Err(XsbError::TooTall { line: line_number }); }
+        rows.push(String::new());
+        Ok(())
+    };
+    new_row(rows)?;
+    let mut count: usize = 0;
+    for c in line.chars() {
+        match c {
+            '0'..='9' => {
+                let digit = c.to_digit(10).unwrap_or(0) as usize;
+                count = count.checked_mul(10).and_then(|count| count.checked_add(digit))
+                    .filter(|&count| count <= MAX_WIDTH)
+                    .ok_or_else(too_wide)?;
+            },
+            '|' => { new_row(rows)?; count = 0; },
+            _ => {
+                let row = rows.last_mut().expect("always at least one row");
+                // Every glyph here is ASCII, so a row's length in bytes is its width.
+                if row.len() + count.max(1) > MAX_WIDTH { return Err(too_wide()); }
+                for _ in 0..count.max(1) { row.push(c); }
+                count = 0;
+            },
+        }
+    }
+    Ok(())
+}
+
+/// The title in a line of text between boards, if it looks like one, and whether it's
+/// spelled out with a `Title:` key.
+fn title_from(line: &str) -> Option<(String, bool)> {
+    let line = line.trim_start_matches(';').trim();
+    let (title, explicit) = match line.split_once(':') {
+        Some((key, value)) if key.trim().eq_ignore_ascii_case("title") => (value.trim(), true),
+        // Other keys, like `Author:` or `Comment:`, aren't titles.
+        Some(_) => { return None; },
+        None => (line, false),
+    };
+    if title.is_empty() { None } else { Some((title.to_string(), explicit)) }
+}
+
+/// Turn one board's rows into the text of a level file.
+fn to_level(rows: &[String]) -> String {
+    let width = rows.iter().map(|row| row.chars().count()).max().unwrap_or(0);
+    let mut grid: Vec<Vec<char>> = rows.iter()
+        .map(|row| {
+            let mut row: Vec<_> = row.chars().collect();
+            row.resize(width, ' ');
+            row
+        })
+        .collect();
+
+    // Floor the player can't reach is the outside of the level, so wall it in.
+    let mut inside = vec![vec![false; width]; grid.len()];
+    let start = grid.iter().enumerate()
+        .find_map(|(y, row)| row.iter().position(|&c| c == '@' || c == '+').map(|x| (x, y)));
+    let mut stack: Vec<_> = start.into_iter().collect();
+    while let Some((x, y)) = stack.pop() {
+        if inside[y][x] || grid[y][x] == '#' { continue; }
+        inside[y][x] = true;
+        if x > 0 { stack.push((x - 1, y)); }
+        if y > 0 { stack.push((x, y - 1)); }
+        if x + 1 < width { stack.push((x + 1, y)); }
+        if y + 1 < grid.len() { stack.push((x, y + 1)); }
+    }
+
+    let mut source = String::from("W\n");
+    for (y, row) in grid.iter_mut().enumerate() {
+        for (x, c) in row.iter_mut().enumerate() {
+            *c = match *c {
+                '@' => 'a',
+                '+' => '+',
+                '$' => 'k',
+                '*' => ';',
+                '.' => ':',
+                ' ' | '-' | '_' if inside[y][x] => '.',
+                _ => '#',
+            };
+        }
+        source.extend(row.iter());
+        source.push('\n');
+    }
+    source
+}
+
+/// Read every board in an XSB collection, in order.
+pub fn import(text: &str) -> Result<Vec<XsbLevel>, XsbError> {
+    let mut levels: Vec<XsbLevel> = Vec::new();
+    // The last line of text since the previous board, for naming the next one.
+    let mut heading = None;
+    // Whether a `Title:` line would still name the previous board, rather than the next.
+    let mut trailing = false;
+    let mut lines = text.lines().map(|line| line.trim_end_matches('\r')).enumerate().peekable();
+    while let Some((idx, line)) = lines.next() {
+        if !is_board_line(line) {
+            match (title_from(line), levels.last_mut()) {
+                (Some((title, true)), Some(level)) if trailing => {
+                    level.title = Some(title);
+                    trailing = false;
+                },
+                (Some((title, _)), _) => {
+                    heading = Some(title);
+                    trailing = false;
+                },
+                (None, _) => (),
+            }
+            continue;
+        }
+        let mut rows = Vec::new();
+        expand(line, idx + 1, &mut rows)?;
+        while let Some(&(next_idx, next)) = lines.peek() {
+            if !is_board_line(next) { break; }
+            expand(next, next_idx + 1, &mut rows)?;
+            lines.next();
+        }
+        let source = to_level(&rows);
+        if let Err(error) = Room::from_file(source.as_bytes()) {
+            return Err(XsbError::Level { line: idx + 1, error });
+        }
+        levels.push(XsbLevel { title: heading.take(), source });
+        trailing = true;
+    }
+    if levels.is_empty() { return Err(XsbError::Empty); }
+    Ok(levels)
+}
+
+/// Write a level out as an XSB board, if it's plain Sokoban: gray blocks, gray goals, walls,
+/// and floor, with no exit.
+pub fn export(puzzle: &Puzzle) -> Result<String, XsbError> {
+    let unsupported = |x: usize, y: usize, what: &str| {
+        XsbError::Unsupported { line: y + 2, column: x + 1, what: what.to_string() }
+    };
+    let width = puzzle.room.width();
+    let mut rows = Vec::new();
+    for y in 0..puzzle.room.height() {
+        let mut row = String::new();
+        for x in 0..width {
+            let tile = &puzzle.room.tiles()[y * width + x];
+            let goal = match tile {
+                Tile::Wall => {
+                    row.push('#');
+                    continue;
+                },
+                Tile::Floor => false,
+                Tile::Goal(Color::Gray) => true,
+                Tile::Goal(_) => { return Err(unsupported(x, y, "a colored goal")); },
+                _ => { return Err(unsupported(x, y, "a tile other than wall, floor, or goal")); },
+            };
+            let (px, py) = (x as i32, y as i32);
+            let piece = puzzle.piece_at(px, py).map(|piece| &piece.kind);
+            let glyph = match piece {
+                None if puzzle.player == (px, py) => if goal { '+' } else { '@' },
+                Some(Kind::Block(Color::Gray)) => if goal { '*' } else { '$' },
+                Some(Kind::Block(_)) => { return Err(unsupported(x, y, "a colored block")); },
+                Some(Kind::Exit) => { return Err(unsupported(x, y, "an exit")); },
+                Some(_) => { return Err(unsupported(x, y, "a piece other than a gray block")); },
+                None => if goal { '.' } else { ' ' },
+            };
+            row.push(glyph);
+        }
+        rows.push(row.trim_end().to_string());
+    }
+    Ok(rows.join("\n") + "\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MICROBAN_1: &str = "####\n# .#\n#  ###\n#*@  #\n#  $ #\n#  ###\n####\n";
+
+    #[test]
+    fn imports_boxes_and_goals_as_gray() {
+        let levels = import(MICROBAN_1).unwrap();
+        assert_eq!(levels.len(), 1);
+        assert_eq!(levels[0].source, "W\n######\n#.:###\n#..###\n#;a..#\n#..k.#\n#..###\n######\n");
+    }
+
+    #[test]
+    fn round_trips_through_a_level() {
+        let levels = import(MICROBAN_1).unwrap();
+        let puzzle = Room::from_file(levels[0].source.as_bytes()).unwrap();
+        let board = export(&puzzle).unwrap();
+        // Floor outside the walls comes back as wall, but the board is otherwise the same.
+        assert_eq!(board, "######\n# .###\n#  ###\n#*@  #\n#  $ #\n#  ###\n######\n");
+        let again = import(&board).unwrap();
+        assert_eq!(again[0].source, levels[0].source);
+    }
+
+    #[test]
+    fn player_on_a_goal_round_trips() {
+        let levels = import("#####\n#+$.#\n#####\n").unwrap();
+        assert_eq!(levels[0].source, "W\n#####\n#+k:#\n#####\n");
+        let puzzle = Room::from_file(levels[0].source.as_bytes()).unwrap();
+        assert_eq!(export(&puzzle).unwrap(), "#####\n#+$.#\n#####\n");
+    }
+
+    #[test]
+    fn titles_come_from_title_keys_or_the_line_before() {
+        let text = "; 1\n#####\n#@$.#\n#####\nTitle: First\nAuthor: Someone\n\nSecond\n#####\n#.$@#\n#####\n\n#####\n#@$.#\n#####\n";
+        let titles: Vec<_> = import(text).unwrap().into_iter().map(|level| level.title).collect();
+        assert_eq!(titles, [Some("First".to_string()), Some("Second".to_string()), None]);
+    }
+
+    #[test]
+    fn expands_run_length_encoding() {
+        let levels = import("5#|#@$.#|5#").unwrap();
+        assert_eq!(levels[0].source, "W\n#####\n#ak:#\n#####\n");
+    }
+
+    #[test]
+    fn rejects_huge_run_lengths_without_expanding_them() {
+        assert_eq!(import("99999999999#").err(), Some(XsbError::TooWide { line: 1 }));
+        assert_eq!(import("#@$.#\n999#999#").err(), Some(XsbError::TooWide { line: 2 }));
+    }
+
+    #[test]
+    fn rejects_boards_with_too_many_rows() {
+        let rows = vec!["9#"; MAX_HEIGHT + 1].join("|");
+        assert_eq!(import(&rows).err(), Some(XsbError::TooTall { line: 1 }));
+        // Across lines too, and however the rows are split up between them.
+        let lines = vec!["#|#"; MAX_HEIGHT / 2 + 1].join("\n");
+        assert_eq!(import(&lines).err(), Some(XsbError::TooTall { line: MAX_HEIGHT / 2 + 1 }));
+        let board = format!("{}|#@$.#|{}", vec!["5#"; MAX_HEIGHT - 3].join("|"), "5#");
+        assert!(import(&board).is_ok());
+    }
+
+    #[test]
+    fn reports_boards_that_dont_load() {
+        assert_eq!(
+            import("title\n#####\n#@  #\n#####\n").err(),
+            Some(XsbError::Level { line: 2, error: LevelError::MissingExit }),
+        );
+        assert_eq!(import("no boards here").err(), Some(XsbError::Empty));
+    }
+
+    #[test]
+    fn wont_export_colorways_pieces() {
+        let puzzle = Room::from_file(b"W\n#####\n#akz#\n#####\n").unwrap();
+        assert_eq!(
+            export(&puzzle).err(),
+            Some(XsbError::Unsupported { line: 3, column: 4, what: "an exit".to_string() }),
+        );
+    }
+}